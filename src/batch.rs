@@ -0,0 +1,205 @@
+//! Batch verification of Ed25519 signatures
+//!
+//! The signatures are checked together, with a single multi-scalar
+//! multiplication computing a linear combination of their cofactored
+//! verification equations with pseudo-random coefficients:
+//!
+//! ```text
+//! [8]([sum z_i S_i]B - sum [z_i]R_i - sum [z_i k_i]A_i) = 0
+//! ```
+//!
+//! The 128 bits coefficients `z_i` are derived deterministically by hashing
+//! the whole batch, rather than drawn from a random number generator: they
+//! are only known once the signatures, the public keys and the messages are
+//! fixed, and the same batch always gets the same coefficients. When the
+//! batch doesn't verify, every signature is verified on its own to find the
+//! invalid ones.
+
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use cryptoxide::curve25519::{Ge, GeCached};
+use cryptoxide::hashing::sha2::Sha512;
+
+use super::key::XPub;
+use super::point;
+use super::scalar::{add_wide, mul_wide, reduce};
+use super::signature::Signature;
+use super::verification::{self, Equation, VerificationMode};
+
+const TRANSCRIPT_DOMAIN: &[u8] = b"ed25519-bip32 batch verification";
+
+/// Invalid signatures of a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchVerificationError(Vec<usize>);
+
+impl BatchVerificationError {
+    /// indices of the invalid signatures in the batch, in increasing order
+    pub fn invalid_indices(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+/// verify a batch of signatures, each given with its public key and message
+///
+/// The result is the same as verifying every signature on its own with
+/// `XPub::verify_with_mode` and the same mode, except with a negligible
+/// probability. In `VerificationMode::Strict` the signatures are verified
+/// one by one: batching the cofactorless equation would accept signatures
+/// that the single verification rejects.
+///
+/// No random number generator is needed: the coefficients of the batch
+/// equation are derived from a hash of the batch, so the result is
+/// deterministic.
+pub fn verify_batch<T>(
+    mode: VerificationMode,
+    batch: &[(XPub, &[u8], Signature<T>)],
+) -> Result<(), BatchVerificationError> {
+    if mode != VerificationMode::Strict && batch_equation_holds(mode, batch) {
+        return Ok(());
+    }
+    let invalid: Vec<usize> = batch
+        .iter()
+        .enumerate()
+        .filter(|(_, (xpub, message, signature))| !xpub.verify_with_mode(message, signature, mode))
+        .map(|(index, _)| index)
+        .collect();
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(BatchVerificationError(invalid))
+    }
+}
+
+fn batch_equation_holds<T>(mode: VerificationMode, batch: &[(XPub, &[u8], Signature<T>)]) -> bool {
+    let mut equations = Vec::with_capacity(batch.len());
+    let mut transcript = Sha512::new().update(TRANSCRIPT_DOMAIN);
+    for (xpub, message, signature) in batch {
+        let public_key = xpub.public_key_bytes();
+        match verification::equation(mode, &[], public_key, message, signature.to_bytes()) {
+            Some(equation) => {
+                // `k` is the hash of the message
+                transcript.update_mut(signature.to_bytes());
+                transcript.update_mut(public_key);
+                transcript.update_mut(&equation.k.to_bytes());
+                equations.push(equation);
+            }
+            None => return false,
+        }
+    }
+    let transcript = transcript.finalize();
+
+    let mut b_coefficient = [0u64; 8];
+    let mut terms = Vec::with_capacity(2 * equations.len());
+    for (index, Equation { neg_a, neg_r, s, k }) in equations.into_iter().enumerate() {
+        let z = coefficient(&transcript, index);
+        add_wide(&mut b_coefficient, &mul_wide(&z, &s.to_bytes()));
+        terms.push((z, neg_r));
+        terms.push((reduce(&mul_wide(&z, &k.to_bytes())).to_bytes(), neg_a));
+    }
+
+    let b_term = Ge::scalarmult_base(&reduce(&b_coefficient));
+    let sum = (&b_term + &multiscalar_mul(&terms).to_cached()).to_full();
+    point::is_small_order(&sum)
+}
+
+/// 128 bits coefficient of the equation at the given index
+///
+/// The products of the coefficients and the scalars are below 2^381, so the
+/// sum of their products doesn't overflow 512 bits in any realistic batch.
+fn coefficient(transcript: &[u8; 64], index: usize) -> [u8; 32] {
+    let hash = Sha512::new()
+        .update(transcript)
+        .update(&(index as u64).to_le_bytes())
+        .finalize();
+    let mut z = [0u8; 32];
+    z[0..16].copy_from_slice(&hash[0..16]);
+    z
+}
+
+/// sum of the `[scalar]point` of the terms, with the Straus method and 4 bits windows
+fn multiscalar_mul(terms: &[([u8; 32], Ge)]) -> Ge {
+    // the multiples 1 to 15 of every point
+    let mut tables: Vec<GeCached> = Vec::with_capacity(15 * terms.len());
+    for (_, point) in terms {
+        let cached = point.to_cached();
+        let mut multiple = point.clone();
+        tables.push(cached.clone());
+        for _ in 1..15 {
+            multiple = (&multiple + &cached).to_full();
+            tables.push(multiple.to_cached());
+        }
+    }
+
+    let mut sum = Ge::ZERO;
+    for window in (0..64).rev() {
+        sum = sum.double_partial().double().double().double_full();
+        for (table, (scalar, _)) in tables.chunks(15).zip(terms.iter()) {
+            let digit = (scalar[window / 2] >> (4 * (window % 2))) & 0x0f;
+            if digit != 0 {
+                sum = (&sum + &table[digit as usize - 1]).to_full();
+            }
+        }
+    }
+    sum
+}
+
+impl fmt::Display for BatchVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid signatures in batch at indices {:?}", self.0)
+    }
+}
+impl Error for BatchVerificationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DerivationScheme, XPrv};
+
+    fn batch(root: &XPrv, messages: &[Vec<u8>]) -> Vec<(XPub, Vec<u8>, Signature<u8>)> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let xprv = root.derive(DerivationScheme::V2, index as u32);
+                (xprv.public(), message.clone(), xprv.sign(message))
+            })
+            .collect()
+    }
+
+    fn equation_holds(mode: VerificationMode, batch: &[(XPub, Vec<u8>, Signature<u8>)]) -> bool {
+        let batch: Vec<(XPub, &[u8], Signature<u8>)> = batch
+            .iter()
+            .map(|(xpub, message, signature)| (*xpub, &message[..], signature.clone()))
+            .collect();
+        batch_equation_holds(mode, &batch)
+    }
+
+    #[test]
+    fn batch_equation() {
+        let root = XPrv::normalize_bytes_force3rd([7u8; 96]);
+        let messages: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 10 * i as usize]).collect();
+        let mut batch = batch(&root, &messages);
+        for mode in [VerificationMode::Cofactored, VerificationMode::Zip215].iter() {
+            assert!(equation_holds(*mode, &batch));
+        }
+
+        // a valid signature of another message
+        batch[3].2 = root
+            .derive(DerivationScheme::V2, 3)
+            .sign(b"another message");
+        for mode in [VerificationMode::Cofactored, VerificationMode::Zip215].iter() {
+            assert!(!equation_holds(*mode, &batch));
+        }
+    }
+
+    #[test]
+    fn deterministic_coefficients() {
+        let transcript = [0x5au8; 64];
+        let z = coefficient(&transcript, 1);
+        assert_eq!(z, coefficient(&transcript, 1));
+        assert_ne!(z, coefficient(&transcript, 2));
+        assert_eq!(&z[16..], &[0u8; 16]);
+    }
+}
@@ -0,0 +1,352 @@
+//! Bech32 encoding (BIP173) of keys and signatures, with the Cardano
+//! human readable prefixes defined in CIP-5.
+//!
+//! Cardano keys are longer than the 90 characters allowed by BIP173,
+//! so no length limit is enforced on the encoded strings.
+
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::key::{PrivateKeyError, XPrv, XPub};
+use super::securemem;
+use super::signature::Signature;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const SEPARATOR: char = '1';
+const CHECKSUM_LENGTH: usize = 6;
+
+/// Possible errors when decoding a bech32 string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bech32Error {
+    MissingSeparator,
+    InvalidHrp,
+    /// the string is valid bech32, but not with the expected prefix
+    UnexpectedHrp {
+        expected: &'static str,
+        found: String,
+    },
+    InvalidCharacter(char),
+    MixedCase,
+    InvalidChecksum,
+    InvalidPadding,
+    /// the decoded data does not have the size of the expected type
+    InvalidDataLength(usize),
+    InvalidPrivateKey(PrivateKeyError),
+}
+
+/// Bech32 human readable part, tagged with the type it is used to encode
+///
+/// The CIP-5 prefixes are available in the `hrp` module, other prefixes
+/// can be created with `Hrp::new` or `Hrp::try_new`. Like the rest of the
+/// bech32 string, the prefix is case insensitive and is encoded in lowercase.
+pub struct Hrp<T: ?Sized> {
+    prefix: &'static str,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+/// Type tag of the `Hrp` used to encode a `Signature`, whatever its own type tag
+pub enum SignatureHrp {}
+
+impl<T: ?Sized> Hrp<T> {
+    /// create a new human readable part, the prefix has to be
+    /// made of 1 or more printable ASCII characters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is invalid, which is a compile time error
+    /// when creating a constant. See `Hrp::try_new` for the fallible version.
+    pub const fn new(prefix: &'static str) -> Self {
+        assert!(is_valid_hrp(prefix), "invalid bech32 human readable part");
+        Hrp {
+            prefix,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// create a new human readable part, returning `Bech32Error::InvalidHrp`
+    /// if the prefix isn't made of 1 or more printable ASCII characters.
+    pub fn try_new(prefix: &'static str) -> Result<Self, Bech32Error> {
+        if is_valid_hrp(prefix) {
+            Ok(Self::new(prefix))
+        } else {
+            Err(Bech32Error::InvalidHrp)
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.prefix
+    }
+}
+impl<T: ?Sized> Clone for Hrp<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Copy for Hrp<T> {}
+impl<T: ?Sized> PartialEq for Hrp<T> {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.prefix.eq_ignore_ascii_case(rhs.prefix)
+    }
+}
+impl<T: ?Sized> Eq for Hrp<T> {}
+impl<T: ?Sized> fmt::Debug for Hrp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.prefix)
+    }
+}
+impl<T: ?Sized> fmt::Display for Hrp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.prefix)
+    }
+}
+
+/// human readable parts defined by CIP-5 for extended keys and signatures
+pub mod hrp {
+    use super::{Hrp, SignatureHrp};
+    use crate::key::{XPrv, XPub};
+
+    pub const ROOT_XSK: Hrp<XPrv> = Hrp::new("root_xsk");
+    pub const ACCT_XSK: Hrp<XPrv> = Hrp::new("acct_xsk");
+    pub const ADDR_XSK: Hrp<XPrv> = Hrp::new("addr_xsk");
+    pub const STAKE_XSK: Hrp<XPrv> = Hrp::new("stake_xsk");
+    pub const ACCT_SHARED_XSK: Hrp<XPrv> = Hrp::new("acct_shared_xsk");
+    pub const ADDR_SHARED_XSK: Hrp<XPrv> = Hrp::new("addr_shared_xsk");
+    pub const STAKE_SHARED_XSK: Hrp<XPrv> = Hrp::new("stake_shared_xsk");
+    pub const DREP_XSK: Hrp<XPrv> = Hrp::new("drep_xsk");
+    pub const CC_COLD_XSK: Hrp<XPrv> = Hrp::new("cc_cold_xsk");
+    pub const CC_HOT_XSK: Hrp<XPrv> = Hrp::new("cc_hot_xsk");
+
+    pub const ROOT_XVK: Hrp<XPub> = Hrp::new("root_xvk");
+    pub const ACCT_XVK: Hrp<XPub> = Hrp::new("acct_xvk");
+    pub const ADDR_XVK: Hrp<XPub> = Hrp::new("addr_xvk");
+    pub const STAKE_XVK: Hrp<XPub> = Hrp::new("stake_xvk");
+    pub const ACCT_SHARED_XVK: Hrp<XPub> = Hrp::new("acct_shared_xvk");
+    pub const ADDR_SHARED_XVK: Hrp<XPub> = Hrp::new("addr_shared_xvk");
+    pub const STAKE_SHARED_XVK: Hrp<XPub> = Hrp::new("stake_shared_xvk");
+    pub const DREP_XVK: Hrp<XPub> = Hrp::new("drep_xvk");
+    pub const CC_COLD_XVK: Hrp<XPub> = Hrp::new("cc_cold_xvk");
+    pub const CC_HOT_XVK: Hrp<XPub> = Hrp::new("cc_hot_xvk");
+
+    pub const ED25519_SIG: Hrp<SignatureHrp> = Hrp::new("ed25519_sig");
+}
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GENERATORS: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes()
+        .map(|b| b >> 5)
+        .chain(core::iter::once(0))
+        .chain(hrp.bytes().map(|b| b & 0x1f))
+}
+
+const fn is_valid_hrp(hrp: &str) -> bool {
+    let bytes = hrp.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] < 33 || bytes[i] > 126 {
+            return false;
+        }
+        i += 1;
+    }
+    !bytes.is_empty()
+}
+
+// regroup the bits of `data` from `from` bits to `to` bits per element
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, Bech32Error> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let max = (1u32 << to) - 1;
+    let max_acc = (1u32 << (from + to - 1)) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &value in data {
+        acc = ((acc << from) | value as u32) & max_acc;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+        securemem::zero(&mut out);
+        return Err(Bech32Error::InvalidPadding);
+    }
+    Ok(out)
+}
+
+/// encode the given data with the given human readable part
+pub(crate) fn encode(hrp: &str, data: &[u8]) -> String {
+    debug_assert!(is_valid_hrp(hrp), "invalid bech32 human readable part");
+    let hrp = hrp.to_ascii_lowercase();
+    let mut values = convert_bits(data, 8, 5, true).expect("padding is always valid");
+
+    let chk = polymod(
+        hrp_expand(&hrp)
+            .chain(values.iter().copied())
+            .chain([0u8; CHECKSUM_LENGTH].iter().copied()),
+    ) ^ 1;
+    for i in 0..CHECKSUM_LENGTH {
+        values.push(((chk >> (5 * (5 - i))) & 0x1f) as u8);
+    }
+
+    let mut out = String::with_capacity(hrp.len() + 1 + values.len());
+    out.push_str(&hrp);
+    out.push(SEPARATOR);
+    out.extend(values.iter().map(|&v| CHARSET[v as usize] as char));
+    out
+}
+
+/// decode the given bech32 string, returning the human readable part
+/// (in lowercase) and the data.
+pub(crate) fn decode(s: &str) -> Result<(String, Vec<u8>), Bech32Error> {
+    let has_lower = s.bytes().any(|b| b.is_ascii_lowercase());
+    let has_upper = s.bytes().any(|b| b.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(Bech32Error::MixedCase);
+    }
+    let lowercase = s.to_ascii_lowercase();
+    let result = decode_lowercase(&lowercase);
+    let mut lowercase = lowercase.into_bytes();
+    securemem::zero(&mut lowercase);
+    result
+}
+
+fn decode_lowercase(s: &str) -> Result<(String, Vec<u8>), Bech32Error> {
+    let position = s.rfind(SEPARATOR).ok_or(Bech32Error::MissingSeparator)?;
+    let (hrp, data) = (&s[..position], &s[position + 1..]);
+    if !is_valid_hrp(hrp) {
+        return Err(Bech32Error::InvalidHrp);
+    }
+    if data.len() < CHECKSUM_LENGTH {
+        return Err(Bech32Error::InvalidChecksum);
+    }
+
+    // the values are wiped like the decoded data, which may be a secret key
+    let mut values = Vec::with_capacity(data.len());
+    for c in data.chars() {
+        match CHARSET.iter().position(|&x| x as char == c) {
+            Some(v) => values.push(v as u8),
+            None => {
+                securemem::zero(&mut values);
+                return Err(Bech32Error::InvalidCharacter(c));
+            }
+        }
+    }
+
+    let result = if polymod(hrp_expand(hrp).chain(values.iter().copied())) != 1 {
+        Err(Bech32Error::InvalidChecksum)
+    } else {
+        convert_bits(&values[..values.len() - CHECKSUM_LENGTH], 5, 8, false)
+            .map(|data| (hrp.to_string(), data))
+    };
+    securemem::zero(&mut values);
+    result
+}
+
+// decode the given string, checking it has the expected human readable part
+fn decode_with_hrp<T: ?Sized>(s: &str, hrp: Hrp<T>) -> Result<Vec<u8>, Bech32Error> {
+    let (found, data) = decode(s)?;
+    if !found.eq_ignore_ascii_case(hrp.as_str()) {
+        return Err(Bech32Error::UnexpectedHrp {
+            expected: hrp.as_str(),
+            found,
+        });
+    }
+    Ok(data)
+}
+
+impl XPrv {
+    /// encode the `XPrv` in bech32 with the given prefix (e.g. `hrp::ROOT_XSK`)
+    pub fn to_bech32(&self, hrp: Hrp<XPrv>) -> String {
+        encode(hrp.as_str(), self.as_ref())
+    }
+
+    /// decode a bech32 `XPrv`, which must have the given prefix.
+    ///
+    /// The decoded key is checked with `XPrv::from_slice_verified`.
+    pub fn from_bech32(s: &str, hrp: Hrp<XPrv>) -> Result<Self, Bech32Error> {
+        let mut data = decode_with_hrp(s, hrp)?;
+        let xprv = XPrv::from_slice_verified(&data).map_err(|e| match e {
+            PrivateKeyError::LengthInvalid(length) => Bech32Error::InvalidDataLength(length),
+            e => Bech32Error::InvalidPrivateKey(e),
+        });
+        securemem::zero(&mut data);
+        xprv
+    }
+}
+
+impl XPub {
+    /// encode the `XPub` in bech32 with the given prefix (e.g. `hrp::ACCT_XVK`)
+    pub fn to_bech32(&self, hrp: Hrp<XPub>) -> String {
+        encode(hrp.as_str(), self.as_ref())
+    }
+
+    /// decode a bech32 `XPub`, which must have the given prefix.
+    pub fn from_bech32(s: &str, hrp: Hrp<XPub>) -> Result<Self, Bech32Error> {
+        let data = decode_with_hrp(s, hrp)?;
+        XPub::from_slice(&data).map_err(|_| Bech32Error::InvalidDataLength(data.len()))
+    }
+}
+
+impl<T> Signature<T> {
+    /// encode the `Signature` in bech32 with the given prefix (e.g. `hrp::ED25519_SIG`)
+    pub fn to_bech32(&self, hrp: Hrp<SignatureHrp>) -> String {
+        encode(hrp.as_str(), self.as_ref())
+    }
+
+    /// decode a bech32 `Signature`, which must have the given prefix.
+    pub fn from_bech32(s: &str, hrp: Hrp<SignatureHrp>) -> Result<Self, Bech32Error> {
+        let data = decode_with_hrp(s, hrp)?;
+        Signature::from_slice(&data).map_err(|_| Bech32Error::InvalidDataLength(data.len()))
+    }
+}
+
+impl fmt::Display for Bech32Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bech32Error::MissingSeparator => f.write_str("Missing bech32 separator"),
+            Bech32Error::InvalidHrp => f.write_str("Invalid bech32 human readable part"),
+            Bech32Error::UnexpectedHrp { expected, found } => write!(
+                f,
+                "Unexpected bech32 prefix, expected {} but got {}",
+                expected, found
+            ),
+            Bech32Error::InvalidCharacter(c) => write!(f, "Invalid bech32 character {:?}", c),
+            Bech32Error::MixedCase => f.write_str("Mixed case bech32 string"),
+            Bech32Error::InvalidChecksum => f.write_str("Invalid bech32 checksum"),
+            Bech32Error::InvalidPadding => f.write_str("Invalid bech32 padding"),
+            Bech32Error::InvalidDataLength(length) => {
+                write!(f, "Invalid bech32 data length {}", length)
+            }
+            Bech32Error::InvalidPrivateKey(e) => write!(f, "Invalid private key: {}", e),
+        }
+    }
+}
+impl Error for Bech32Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Bech32Error::InvalidPrivateKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -5,6 +5,7 @@ fn new_sk() -> XPrv {
     XPrv::normalize_bytes_force3rd(b)
 }
 
+#[cfg(feature = "legacy-v1")]
 #[bench]
 fn derivate_hard_v1(b: &mut test::Bencher) {
     let sk = new_sk();
@@ -20,6 +21,7 @@ fn derivate_hard_v2(b: &mut test::Bencher) {
     })
 }
 
+#[cfg(feature = "legacy-v1")]
 #[bench]
 fn derivate_soft_v1_xprv(b: &mut test::Bencher) {
     let sk = new_sk();
@@ -34,6 +36,7 @@ fn derivate_soft_v2_xprv(b: &mut test::Bencher) {
         let _ = sk.derive(DerivationScheme::V2, 0);
     })
 }
+#[cfg(feature = "legacy-v1")]
 #[bench]
 fn derivate_soft_v1_xpub(b: &mut test::Bencher) {
     let sk = new_sk();
@@ -50,3 +53,56 @@ fn derivate_soft_v2_xpub(b: &mut test::Bencher) {
         let _ = pk.derive(DerivationScheme::V2, 0);
     })
 }
+#[bench]
+fn derivate_soft_v2_prepared_xpub(b: &mut test::Bencher) {
+    let sk = new_sk();
+    let pk = PreparedXPub::new(&sk.public(), DerivationScheme::V2).unwrap();
+    b.iter(|| {
+        let _ = pk.derive(0);
+    })
+}
+#[bench]
+fn derivate_soft_v2_xpub_100(b: &mut test::Bencher) {
+    let sk = new_sk();
+    let pk = sk.public();
+    b.iter(|| {
+        for i in 0..100 {
+            let _ = pk.derive(DerivationScheme::V2, i);
+        }
+    })
+}
+#[bench]
+fn derivate_soft_v2_prepared_xpub_100(b: &mut test::Bencher) {
+    let sk = new_sk();
+    let pk = PreparedXPub::new(&sk.public(), DerivationScheme::V2).unwrap();
+    b.iter(|| {
+        let _ = pk.derive_range(0..100);
+    })
+}
+
+fn signed_batch() -> Vec<(XPub, &'static [u8], Signature<u8>)> {
+    let sk = new_sk();
+    (0..100)
+        .map(|i| {
+            let child = sk.derive(DerivationScheme::V2, i);
+            let message: &'static [u8] = b"batch verification benchmark";
+            (child.public(), message, child.sign(message))
+        })
+        .collect()
+}
+#[bench]
+fn verify_100(b: &mut test::Bencher) {
+    let batch = signed_batch();
+    b.iter(|| {
+        for (pk, message, signature) in batch.iter() {
+            let _ = pk.verify_with_mode(message, signature, VerificationMode::Cofactored);
+        }
+    })
+}
+#[bench]
+fn verify_batch_100(b: &mut test::Bencher) {
+    let batch = signed_batch();
+    b.iter(|| {
+        let _ = verify_batch(VerificationMode::Cofactored, &batch);
+    })
+}
@@ -0,0 +1,237 @@
+//! BIP39 mnemonic phrase handling
+//!
+//! Only the english word list is supported. Mnemonic phrases are decoded back
+//! to their entropy, which is then used by the master key generation
+//! algorithms of `XPrv` (e.g. `XPrv::from_mnemonic_icarus`).
+
+mod english;
+
+use core::error::Error;
+use core::fmt;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use cryptoxide::hashing::sha256;
+use cryptoxide::{hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+
+use super::securemem;
+
+/// Maximum BIP39 entropy size in bytes (24 words)
+pub const MAX_ENTROPY_SIZE: usize = 32;
+
+/// BIP39 seed size in bytes
+pub const SEED_SIZE: usize = 64;
+
+/// Possible errors when decoding a mnemonic phrase or creating an `Entropy`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bip39Error {
+    /// the number of words is not one of 12, 15, 18, 21 or 24
+    InvalidWordCount(usize),
+    /// the word at the given position is not in the word list
+    UnknownWord(usize),
+    /// the entropy size is not one of 16, 20, 24, 28 or 32 bytes
+    InvalidEntropySize(usize),
+    InvalidChecksum,
+}
+
+/// Entropy of a BIP39 mnemonic phrase
+///
+/// The entropy is a secret: it is zeroed when dropped and is not displayed
+/// by its `Debug` implementation.
+pub struct Entropy {
+    bytes: [u8; MAX_ENTROPY_SIZE],
+    len: usize,
+}
+
+/// BIP39 seed, the result of stretching a mnemonic phrase and its passphrase
+///
+/// The seed is a secret: it is zeroed when dropped and is not displayed
+/// by its `Debug` implementation.
+pub struct Seed([u8; SEED_SIZE]);
+
+fn is_valid_entropy_size(len: usize) -> bool {
+    matches!(len, 16 | 20 | 24 | 28 | 32)
+}
+
+// number of checksum bits for a given entropy size in bytes
+fn checksum_bits(len: usize) -> usize {
+    len * 8 / 32
+}
+
+fn word_index(word: &str) -> Option<u16> {
+    english::WORDS
+        .binary_search(&word)
+        .ok()
+        .map(|index| index as u16)
+}
+
+impl Entropy {
+    /// create an `Entropy` from the given bytes, which must be of size
+    /// 16, 20, 24, 28 or 32 bytes.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, Bip39Error> {
+        if !is_valid_entropy_size(bytes.len()) {
+            return Err(Bip39Error::InvalidEntropySize(bytes.len()));
+        }
+        let mut buf = [0u8; MAX_ENTROPY_SIZE];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Ok(Entropy {
+            bytes: buf,
+            len: bytes.len(),
+        })
+    }
+
+    /// decode a mnemonic phrase, checking every word is in the word list
+    /// and that the checksum is valid.
+    ///
+    /// words can be separated by any whitespace.
+    pub fn from_mnemonic(phrase: &str) -> Result<Self, Bip39Error> {
+        let word_count = phrase.split_whitespace().count();
+        if !matches!(word_count, 12 | 15 | 18 | 21 | 24) {
+            return Err(Bip39Error::InvalidWordCount(word_count));
+        }
+
+        // every word is 11 bits: the entropy followed by the checksum bits
+        let mut bits = [0u8; MAX_ENTROPY_SIZE + 1];
+        for (position, word) in phrase.split_whitespace().enumerate() {
+            let index = match word_index(word) {
+                Some(index) => index,
+                None => {
+                    securemem::zero(&mut bits);
+                    return Err(Bip39Error::UnknownWord(position));
+                }
+            };
+            for bit in 0..11 {
+                if index & (1 << (10 - bit)) != 0 {
+                    let offset = position * 11 + bit;
+                    bits[offset / 8] |= 0x80 >> (offset % 8);
+                }
+            }
+        }
+
+        let len = word_count * 4 / 3;
+        let entropy = Entropy::from_slice(&bits[..len])?;
+        let cs_bits = checksum_bits(len);
+        let mask = 0xffu8 << (8 - cs_bits);
+        let valid = (entropy.checksum() & mask) == (bits[len] & mask);
+        securemem::zero(&mut bits);
+        if valid {
+            Ok(entropy)
+        } else {
+            Err(Bip39Error::InvalidChecksum)
+        }
+    }
+
+    /// encode the entropy as a mnemonic phrase, words separated by a single space
+    pub fn to_mnemonic(&self) -> String {
+        let mut bits = [0u8; MAX_ENTROPY_SIZE + 1];
+        bits[..self.len].copy_from_slice(self.as_ref());
+        bits[self.len] = self.checksum();
+
+        let word_count = self.len * 3 / 4;
+        let mut words = Vec::with_capacity(word_count);
+        for position in 0..word_count {
+            let mut index = 0usize;
+            for bit in 0..11 {
+                let offset = position * 11 + bit;
+                let set = bits[offset / 8] & (0x80 >> (offset % 8)) != 0;
+                index = (index << 1) | set as usize;
+            }
+            words.push(english::WORDS[index]);
+        }
+        securemem::zero(&mut bits);
+        words.join(" ")
+    }
+
+    /// compute the BIP39 seed of the mnemonic phrase of this entropy
+    ///
+    /// The seed is PBKDF2-HMAC-SHA512 (2048 iterations) of the mnemonic phrase,
+    /// salted with `"mnemonic"` followed by the passphrase. The passphrase is
+    /// used as is and should already be normalized by the caller.
+    pub fn to_seed(&self, passphrase: &[u8]) -> Seed {
+        let phrase = self.to_mnemonic();
+        let mut salt = Vec::with_capacity(8 + passphrase.len());
+        salt.extend_from_slice(b"mnemonic");
+        salt.extend_from_slice(passphrase);
+
+        let mut mac = Hmac::new(Sha512::new(), phrase.as_bytes());
+        let mut seed = [0u8; SEED_SIZE];
+        pbkdf2(&mut mac, &salt, 2048, &mut seed);
+
+        let mut phrase = phrase.into_bytes();
+        securemem::zero(&mut phrase);
+        securemem::zero(&mut salt);
+        Seed(seed)
+    }
+
+    // first byte of the SHA256 of the entropy, of which only
+    // the `checksum_bits` highest bits are used.
+    pub(crate) fn checksum(&self) -> u8 {
+        sha256(self.as_ref())[0]
+    }
+}
+
+impl AsRef<[u8]> for Entropy {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+impl Clone for Entropy {
+    fn clone(&self) -> Self {
+        Entropy::from_slice(self.as_ref()).expect("it is already a valid Entropy")
+    }
+}
+impl fmt::Debug for Entropy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Entropy({} bytes)", self.len)
+    }
+}
+impl Drop for Entropy {
+    fn drop(&mut self) {
+        securemem::zero(&mut self.bytes);
+    }
+}
+
+impl Seed {
+    /// create a `Seed` by taking ownership of the given array
+    pub fn from_bytes(bytes: [u8; SEED_SIZE]) -> Self {
+        Seed(bytes)
+    }
+}
+impl AsRef<[u8]> for Seed {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Seed")
+    }
+}
+impl Drop for Seed {
+    fn drop(&mut self) {
+        securemem::zero(&mut self.0);
+    }
+}
+
+impl fmt::Display for Bip39Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bip39Error::InvalidWordCount(count) => write!(
+                f,
+                "Invalid mnemonic word count, expected 12, 15, 18, 21 or 24 but got {}",
+                count
+            ),
+            Bip39Error::UnknownWord(position) => {
+                write!(f, "Unknown mnemonic word at position {}", position)
+            }
+            Bip39Error::InvalidEntropySize(size) => write!(
+                f,
+                "Invalid entropy size, expected 16, 20, 24, 28 or 32 bytes but got {}",
+                size
+            ),
+            Bip39Error::InvalidChecksum => f.write_str("Invalid mnemonic checksum"),
+        }
+    }
+}
+impl Error for Bip39Error {}
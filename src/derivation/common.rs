@@ -1,38 +1,167 @@
+use core::{error::Error, fmt, str::FromStr};
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum DerivationType {
     Soft(u32),
     Hard(u32),
 }
 
+const HARDENED_OFFSET: u32 = 0x80000000;
+
 /// Derivation index is a 32 bits number representing
 /// a type of derivation and a 31 bits number.
 ///
 /// The highest bit set represent a hard derivation,
 /// whereas the bit clear represent soft derivation.
-pub type DerivationIndex = u32;
+///
+/// Use `DerivationIndex::soft` and `DerivationIndex::hard` to build
+/// an index from its 31 bits number, or `From<u32>` to build it from
+/// the raw 32 bits value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DerivationIndex(u32);
+
+/// Possible errors when building or parsing a `DerivationIndex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationIndexError {
+    Empty,
+    InvalidCharacter,
+    OutOfRange,
+}
+
+impl DerivationIndex {
+    /// create a soft derivation index, the given number must be below 2^31
+    pub fn soft(index: u32) -> Result<Self, DerivationIndexError> {
+        if index >= HARDENED_OFFSET {
+            return Err(DerivationIndexError::OutOfRange);
+        }
+        Ok(DerivationIndex(index))
+    }
+
+    /// create a hard derivation index, the given number must be below 2^31
+    /// and will have the highest bit set.
+    pub fn hard(index: u32) -> Result<Self, DerivationIndexError> {
+        if index >= HARDENED_OFFSET {
+            return Err(DerivationIndexError::OutOfRange);
+        }
+        Ok(DerivationIndex(index | HARDENED_OFFSET))
+    }
+
+    /// create a derivation index from its raw 32 bits value
+    pub const fn from_raw(raw: u32) -> Self {
+        DerivationIndex(raw)
+    }
+
+    /// the raw 32 bits value, with the highest bit set for hard derivation
+    pub const fn raw(self) -> u32 {
+        self.0
+    }
+
+    /// the 31 bits number, without the hardened marker
+    pub const fn number(self) -> u32 {
+        self.0 & !HARDENED_OFFSET
+    }
+
+    pub const fn is_hardened(self) -> bool {
+        self.0 >= HARDENED_OFFSET
+    }
+
+    /// the next index of the same derivation type
+    ///
+    /// returns `None` instead of overflowing, a soft index never
+    /// becomes a hard index.
+    pub fn next(self) -> Option<Self> {
+        let next = self.number().checked_add(1)?;
+        if next >= HARDENED_OFFSET {
+            return None;
+        }
+        Some(DerivationIndex(next | (self.0 & HARDENED_OFFSET)))
+    }
+}
+
+impl From<u32> for DerivationIndex {
+    fn from(raw: u32) -> Self {
+        DerivationIndex(raw)
+    }
+}
+
+impl From<DerivationIndex> for u32 {
+    fn from(index: DerivationIndex) -> Self {
+        index.0
+    }
+}
+
+impl fmt::Display for DerivationIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_hardened() {
+            write!(f, "{}'", self.number())
+        } else {
+            write!(f, "{}", self.number())
+        }
+    }
+}
+
+impl FromStr for DerivationIndex {
+    type Err = DerivationIndexError;
+
+    /// parse a derivation index, hardened indices are
+    /// marked with one of `'`, `h` or `H` (e.g. `44'`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, hardened) = match s.strip_suffix(&['\'', 'h', 'H'][..]) {
+            Some(number) => (number, true),
+            None => (s, false),
+        };
+        if number.is_empty() {
+            return Err(DerivationIndexError::Empty);
+        }
+        if !number.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(DerivationIndexError::InvalidCharacter);
+        }
+        let number = number
+            .parse::<u32>()
+            .map_err(|_| DerivationIndexError::OutOfRange)?;
+        if hardened {
+            DerivationIndex::hard(number)
+        } else {
+            DerivationIndex::soft(number)
+        }
+    }
+}
+
+impl fmt::Display for DerivationIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DerivationIndexError::Empty => f.write_str("Empty derivation index"),
+            DerivationIndexError::InvalidCharacter => {
+                f.write_str("Invalid character in derivation index")
+            }
+            DerivationIndexError::OutOfRange => {
+                f.write_str("Derivation index out of range, expected a number below 2^31")
+            }
+        }
+    }
+}
+impl Error for DerivationIndexError {}
 
 impl DerivationType {
     pub fn from_index(index: DerivationIndex) -> Self {
-        if index >= 0x80000000 {
-            DerivationType::Hard(index)
+        if index.is_hardened() {
+            DerivationType::Hard(index.raw())
         } else {
-            DerivationType::Soft(index)
+            DerivationType::Soft(index.raw())
         }
     }
 }
 
 /// Ed25519-bip32 Scheme Derivation version
 ///
-/// Only V2 is supported anymore, and this is
-/// left as an API compatibility type. V1 has
-/// been removed due to some shortcomings
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// V2 is the only scheme to use. V1 has some shortcomings and is only
+/// available with the `legacy-v1` feature, to derive the keys of legacy
+/// wallets and migrate their funds to V2 keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum DerivationScheme {
+    /// legacy Byron derivation, do *not* use it for new keys
+    #[cfg(feature = "legacy-v1")]
+    V1,
+    #[default]
     V2,
 }
-
-impl Default for DerivationScheme {
-    fn default() -> Self {
-        DerivationScheme::V2
-    }
-}
@@ -0,0 +1,48 @@
+use cryptoxide::hashing::sha2::{Context512, Sha512};
+
+use super::super::key::CHAIN_CODE_SIZE;
+use super::super::securemem;
+
+const SHA512_BLOCK_SIZE: usize = 128;
+
+// HMAC-SHA512 keyed with a chain code, the inner and outer hash contexts
+// have already absorbed the padded key and are cloned for every message.
+//
+// The messages may contain the secret key of the parent: the contexts
+// that absorbed them are overwritten once the result is computed.
+#[derive(Clone)]
+pub(crate) struct KeyedHmac {
+    inner: Context512,
+    outer: Context512,
+}
+
+impl KeyedHmac {
+    pub(crate) fn new(key: &[u8; CHAIN_CODE_SIZE]) -> Self {
+        let mut ipad = [0x36u8; SHA512_BLOCK_SIZE];
+        let mut opad = [0x5cu8; SHA512_BLOCK_SIZE];
+        for (i, k) in key.iter().enumerate() {
+            ipad[i] ^= k;
+            opad[i] ^= k;
+        }
+        KeyedHmac {
+            inner: Sha512::new().update(&ipad),
+            outer: Sha512::new().update(&opad),
+        }
+    }
+
+    // HMAC of `tag || data || seri` written to `out`
+    pub(crate) fn mac(&self, tag: u8, data: &[u8], seri: &[u8; 4], out: &mut [u8; 64]) {
+        let mut inner = self.inner.clone();
+        inner.update_mut(&[tag]);
+        inner.update_mut(data);
+        inner.update_mut(seri);
+        let mut inner_hash = inner.finalize_reset();
+        securemem::overwrite(&mut inner, Sha512::new());
+
+        let mut outer = self.outer.clone();
+        outer.update_mut(&inner_hash);
+        *out = outer.finalize_reset();
+        securemem::overwrite(&mut outer, Sha512::new());
+        securemem::zero(&mut inner_hash);
+    }
+}
@@ -1,133 +1,187 @@
 mod common;
+mod hmac;
+mod path;
+mod prepared;
+#[cfg(feature = "legacy-v1")]
+pub mod v1;
 pub mod v2;
 
-use cryptoxide::curve25519::{Ge, Scalar};
-use cryptoxide::hmac::Hmac;
-use cryptoxide::mac::Mac;
-use cryptoxide::sha2::Sha512;
-use std::{
-    convert::{TryFrom, TryInto},
-    error::Error,
-    fmt,
-};
+use core::{convert::TryFrom, error::Error, fmt};
+use cryptoxide::curve25519::Scalar;
 
 use super::key::{mk_public_key, mk_xprv, mk_xpub, XPrv, XPub, XPRV_SIZE, XPUB_SIZE};
-pub use common::{DerivationIndex, DerivationScheme, DerivationType};
+use super::securemem;
+pub use common::{DerivationIndex, DerivationIndexError, DerivationScheme, DerivationType};
+use hmac::KeyedHmac;
+pub use path::{DerivationPath, DerivationPathError, DerivationPathParseError};
+pub use prepared::PreparedXPub;
+
+/// Maximum depth, counted from the master key, up to which the BIP32-Ed25519
+/// paper guarantees that derived private scalars stay valid.
+pub const MAX_DERIVATION_DEPTH: u32 = 1 << 20;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DerivationError {
     InvalidAddition,
     ExpectedSoftDerivation,
+    /// the child would be at the given depth, beyond `MAX_DERIVATION_DEPTH`
+    DepthExceeded(u32),
+    /// the derived private scalar is not a multiple of 8 in the range 2^254..2^255
+    InvalidScalar,
 }
 
 fn add_256bits(x: &[u8; 32], y: &[u8; 32], scheme: DerivationScheme) -> [u8; 32] {
     match scheme {
         DerivationScheme::V2 => v2::add_256bits_v2(x, y),
+        #[cfg(feature = "legacy-v1")]
+        DerivationScheme::V1 => v1::add_256bits_v1(x, y),
     }
 }
 
 fn add_28_mul8(x: &[u8; 32], y: &[u8; 32], scheme: DerivationScheme) -> [u8; 32] {
     match scheme {
         DerivationScheme::V2 => v2::add_28_mul8_v2(x, y),
+        #[cfg(feature = "legacy-v1")]
+        DerivationScheme::V1 => v1::add_28_mul8_v1(x, y),
     }
 }
 
 fn serialize_index(i: u32, derivation_scheme: DerivationScheme) -> [u8; 4] {
     match derivation_scheme {
         DerivationScheme::V2 => v2::le32(i),
+        #[cfg(feature = "legacy-v1")]
+        DerivationScheme::V1 => v1::be32(i),
     }
 }
 
-pub fn private(xprv: &XPrv, index: DerivationIndex, scheme: DerivationScheme) -> XPrv {
-    /*
-     * If so (hardened child):
-     *    let Z = HMAC-SHA512(Key = cpar, Data = 0x00 || ser256(left(kpar)) || ser32(i)).
-     *    let I = HMAC-SHA512(Key = cpar, Data = 0x01 || ser256(left(kpar)) || ser32(i)).
-     * If not (normal child):
-     *    let Z = HMAC-SHA512(Key = cpar, Data = 0x02 || serP(point(kpar)) || ser32(i)).
-     *    let I = HMAC-SHA512(Key = cpar, Data = 0x03 || serP(point(kpar)) || ser32(i)).
-     **/
-
-    let ekey = xprv.extended_secret_key_bytes();
-    let kl: &[u8; 32] = &ekey[0..32].try_into().unwrap();
-    let kr: &[u8; 32] = &ekey[32..64].try_into().unwrap();
-    let chaincode = &xprv.as_ref()[64..96];
-
-    let mut zmac = Hmac::new(Sha512::new(), &chaincode);
-    let mut imac = Hmac::new(Sha512::new(), &chaincode);
-    let seri = serialize_index(index, scheme);
-    match DerivationType::from_index(index) {
-        DerivationType::Soft(_) => {
-            let pk = mk_public_key(ekey);
-            zmac.input(&[0x2]);
-            zmac.input(&pk);
-            zmac.input(&seri);
-            imac.input(&[0x3]);
-            imac.input(&pk);
-            imac.input(&seri);
-        }
-        DerivationType::Hard(_) => {
-            zmac.input(&[0x0]);
-            zmac.input(ekey);
-            zmac.input(&seri);
-            imac.input(&[0x1]);
-            imac.input(ekey);
-            imac.input(&seri);
-        }
-    };
-
-    let mut zout = [0u8; 64];
-    zmac.raw_result(&mut zout);
-    let zl: &[u8; 32] = &zout[0..32].try_into().unwrap();
-    let zr: &[u8; 32] = &zout[32..64].try_into().unwrap();
-
-    // left = kl + 8 * trunc28(zl)
-    let left = add_28_mul8(kl, zl, scheme);
-    // right = zr + kr
-    let right = add_256bits(kr, zr, scheme);
+/// Keyed state for the derivation of the children of a private key
+///
+/// The HMAC keys are set up once from the chain code, and the public key
+/// of the parent is only computed once for all the soft derivations.
+///
+/// No copy of the parent secret key is made, and the intermediate
+/// values of the derivation are zeroed.
+pub(crate) struct PrivateParent<'a> {
+    xprv: &'a XPrv,
+    scheme: DerivationScheme,
+    public_key: Option<[u8; 32]>,
+    hmac: KeyedHmac,
+}
 
-    // note: we don't perform the check for curve order divisibility because it will not happen:
-    // 1. all keys are in the range K=2^254 .. 2^255 (actually the even smaller range 2^254+2^253)
-    // 2. all keys are also multiple of 8
-    // 3. all existing multiple of the curve order n in the range of K are not multiple of 8
+impl<'a> PrivateParent<'a> {
+    pub(crate) fn new(xprv: &'a XPrv, scheme: DerivationScheme) -> Self {
+        PrivateParent {
+            xprv,
+            scheme,
+            public_key: None,
+            hmac: KeyedHmac::new(xprv.chain_code()),
+        }
+    }
 
-    let mut iout = [0u8; 64];
-    imac.raw_result(&mut iout);
-    let cc = &iout[32..];
+    pub(crate) fn derive(&mut self, index: DerivationIndex) -> XPrv {
+        /*
+         * If so (hardened child):
+         *    let Z = HMAC-SHA512(Key = cpar, Data = 0x00 || ser256(left(kpar)) || ser32(i)).
+         *    let I = HMAC-SHA512(Key = cpar, Data = 0x01 || ser256(left(kpar)) || ser32(i)).
+         * If not (normal child):
+         *    let Z = HMAC-SHA512(Key = cpar, Data = 0x02 || serP(point(kpar)) || ser32(i)).
+         *    let I = HMAC-SHA512(Key = cpar, Data = 0x03 || serP(point(kpar)) || ser32(i)).
+         **/
+
+        let scheme = self.scheme;
+        let ekey = self.xprv.extended_secret_key_bytes();
+        let kl = <&[u8; 32]>::try_from(&ekey[0..32]).unwrap();
+        let kr = <&[u8; 32]>::try_from(&ekey[32..64]).unwrap();
+
+        let mut zout = [0u8; 64];
+        let mut iout = [0u8; 64];
+        let seri = serialize_index(index.raw(), scheme);
+        match DerivationType::from_index(index) {
+            DerivationType::Soft(_) => {
+                let pk = self.public_key.get_or_insert_with(|| mk_public_key(ekey));
+                self.hmac.mac(0x2, pk, &seri, &mut zout);
+                self.hmac.mac(0x3, pk, &seri, &mut iout);
+            }
+            DerivationType::Hard(_) => {
+                self.hmac.mac(0x0, ekey, &seri, &mut zout);
+                self.hmac.mac(0x1, ekey, &seri, &mut iout);
+            }
+        };
+
+        let zl = <&[u8; 32]>::try_from(&zout[0..32]).unwrap();
+        let zr = <&[u8; 32]>::try_from(&zout[32..64]).unwrap();
+
+        // left = kl + 8 * trunc28(zl)
+        let mut left = add_28_mul8(kl, zl, scheme);
+        // right = zr + kr
+        let mut right = add_256bits(kr, zr, scheme);
+
+        // note: we don't perform the check for curve order divisibility because it will not happen:
+        // 1. all keys are in the range K=2^254 .. 2^255 (actually the even smaller range 2^254+2^253)
+        // 2. all keys are also multiple of 8
+        // 3. all existing multiple of the curve order n in the range of K are not multiple of 8
+        // 1. and 2. only hold up to `MAX_DERIVATION_DEPTH`, `private_checked` verifies them.
+
+        let cc = &iout[32..];
+
+        let mut out = [0u8; XPRV_SIZE];
+        mk_xprv(&mut out, &left, &right, cc);
+        let xprv = XPrv::from_bytes(out);
+
+        securemem::zero(&mut zout);
+        securemem::zero(&mut iout);
+        securemem::zero(&mut left);
+        securemem::zero(&mut right);
+        securemem::zero(&mut out);
+        xprv
+    }
+}
 
-    let mut out = [0u8; XPRV_SIZE];
-    mk_xprv(&mut out, &left, &right, cc);
+pub fn private(xprv: &XPrv, index: DerivationIndex, scheme: DerivationScheme) -> XPrv {
+    PrivateParent::new(xprv, scheme).derive(index)
+}
 
-    imac.reset();
-    zmac.reset();
+fn check_depth(depth: u32) -> Result<(), DerivationError> {
+    if depth >= MAX_DERIVATION_DEPTH {
+        return Err(DerivationError::DepthExceeded(depth.saturating_add(1)));
+    }
+    Ok(())
+}
 
-    XPrv::from_bytes(out)
+// check the invariants of the private scalar kl: multiple of 8 and
+// in the range 2^254 .. 2^255, which also means kl is not a multiple
+// of the curve order.
+fn check_scalar(kl: &[u8]) -> Result<(), DerivationError> {
+    if (kl[0] & 0b0000_0111) != 0 || (kl[31] & 0b1100_0000) != 0b0100_0000 {
+        return Err(DerivationError::InvalidScalar);
+    }
+    Ok(())
 }
 
-fn point_of_trunc28_mul8(sk: &[u8; 32], scheme: DerivationScheme) -> [u8; 32] {
-    let copy = add_28_mul8(&[0u8; 32], sk, scheme);
-    let scalar = Scalar::from_bytes(&copy);
-    let a = Ge::scalarmult_base(&scalar);
-    a.to_bytes()
+/// same as `private`, for a parent key at the given depth (0 for a master key)
+///
+/// The derivation is refused beyond `MAX_DERIVATION_DEPTH`, and the private
+/// scalar of the child is checked to be valid. V1 keys don't have
+/// these invariants, so only the depth is checked for V1.
+pub fn private_checked(
+    xprv: &XPrv,
+    depth: u32,
+    index: DerivationIndex,
+    scheme: DerivationScheme,
+) -> Result<XPrv, DerivationError> {
+    check_depth(depth)?;
+    let child = private(xprv, index, scheme);
+    match scheme {
+        #[cfg(feature = "legacy-v1")]
+        DerivationScheme::V1 => {}
+        DerivationScheme::V2 => check_scalar(&child.as_ref()[0..32])?,
+    }
+    Ok(child)
 }
 
-fn point_plus(p1: &[u8; 32], p2: &[u8; 32]) -> Result<[u8; 32], DerivationError> {
-    let a = match Ge::from_bytes(p1) {
-        Some(g) => g,
-        None => {
-            return Err(DerivationError::InvalidAddition);
-        }
-    };
-    let b = match Ge::from_bytes(p2) {
-        Some(g) => g,
-        None => {
-            return Err(DerivationError::InvalidAddition);
-        }
-    };
-    let r = &a + &b.to_cached();
-    let mut r = r.to_full().to_bytes();
-    r[31] ^= 0x80;
-    Ok(r)
+fn scalar_of_trunc28_mul8(sk: &[u8; 32], scheme: DerivationScheme) -> Scalar {
+    let copy = add_28_mul8(&[0u8; 32], sk, scheme);
+    Scalar::from_bytes(&copy)
 }
 
 pub fn public(
@@ -135,45 +189,23 @@ pub fn public(
     index: DerivationIndex,
     scheme: DerivationScheme,
 ) -> Result<XPub, DerivationError> {
-    let pk = <&[u8; 32]>::try_from(&xpub.as_ref()[0..32]).unwrap();
-    let chaincode = &xpub.as_ref()[32..64];
-
-    let mut zmac = Hmac::new(Sha512::new(), &chaincode);
-    let mut imac = Hmac::new(Sha512::new(), &chaincode);
-    let seri = serialize_index(index, scheme);
-    match DerivationType::from_index(index) {
-        DerivationType::Soft(_) => {
-            zmac.input(&[0x2]);
-            zmac.input(pk);
-            zmac.input(&seri);
-            imac.input(&[0x3]);
-            imac.input(pk);
-            imac.input(&seri);
-        }
-        DerivationType::Hard(_) => {
-            return Err(DerivationError::ExpectedSoftDerivation);
-        }
-    };
-
-    let mut zout = [0u8; 64];
-    zmac.raw_result(&mut zout);
-    let zl = <&[u8; 32]>::try_from(&zout[0..32]).unwrap();
-    let _zr = &zout[32..64];
-
-    // left = kl + 8 * trunc28(zl)
-    let left = point_plus(pk, &point_of_trunc28_mul8(zl, scheme))?;
-
-    let mut iout = [0u8; 64];
-    imac.raw_result(&mut iout);
-    let cc = &iout[32..];
-
-    let mut out = [0u8; XPUB_SIZE];
-    mk_xpub(&mut out, &left, cc);
-
-    imac.reset();
-    zmac.reset();
+    if index.is_hardened() {
+        return Err(DerivationError::ExpectedSoftDerivation);
+    }
+    PreparedXPub::new(xpub, scheme)?.derive(index)
+}
 
-    Ok(XPub::from_bytes(out))
+/// same as `public`, for a parent key at the given depth (0 for a master key)
+///
+/// The derivation is refused beyond `MAX_DERIVATION_DEPTH`.
+pub fn public_checked(
+    xpub: &XPub,
+    depth: u32,
+    index: DerivationIndex,
+    scheme: DerivationScheme,
+) -> Result<XPub, DerivationError> {
+    check_depth(depth)?;
+    public(xpub, index, scheme)
 }
 
 impl fmt::Display for DerivationError {
@@ -181,6 +213,12 @@ impl fmt::Display for DerivationError {
         match self {
             DerivationError::InvalidAddition => f.write_str("Invalid addition"),
             DerivationError::ExpectedSoftDerivation => f.write_str("Expected a soft derivation"),
+            DerivationError::DepthExceeded(depth) => write!(
+                f,
+                "Derivation depth {} exceeds the safe limit of {}",
+                depth, MAX_DERIVATION_DEPTH
+            ),
+            DerivationError::InvalidScalar => f.write_str("Invalid derived private scalar"),
         }
     }
 }
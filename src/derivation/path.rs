@@ -0,0 +1,219 @@
+use core::{error::Error, fmt, str::FromStr};
+
+use alloc::vec::Vec;
+
+use super::common::{DerivationIndex, DerivationIndexError};
+use super::DerivationError;
+
+/// A derivation path, a sequence of `DerivationIndex` starting from a root key
+///
+/// The textual representation follows the usual BIP32 notation, e.g.
+/// `m/1852'/1815'/0'/0/5`, where hardened indices are marked with
+/// one of `'`, `h` or `H`. The leading `m` is optional when parsing,
+/// but always present when formatting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DerivationPath(Vec<DerivationIndex>);
+
+/// Possible errors when parsing a `DerivationPath` from a string
+///
+/// The `usize` is the position of the offending component in the path,
+/// not counting the optional `m` root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationPathParseError {
+    EmptyComponent(usize),
+    InvalidComponent(usize),
+    IndexOutOfRange(usize),
+}
+
+/// Error reported when deriving a key along a `DerivationPath`
+///
+/// `position` is the position in the path of the component
+/// that could not be derived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPathError {
+    pub position: usize,
+    pub error: DerivationError,
+}
+
+impl DerivationPath {
+    /// create an empty path, representing the root key itself
+    pub fn new() -> Self {
+        DerivationPath(Vec::new())
+    }
+
+    /// number of derivation steps in the path
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// append an index at the end of the path
+    pub fn push<I: Into<DerivationIndex>>(&mut self, index: I) {
+        self.0.push(index.into())
+    }
+
+    /// remove the last index of the path, if any
+    pub fn pop(&mut self) -> Option<DerivationIndex> {
+        self.0.pop()
+    }
+
+    /// the path of the parent key, or `None` if this is the root path
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.0.split_last()?;
+        Some(DerivationPath(parent.to_vec()))
+    }
+
+    /// the path of the child key at the given index
+    pub fn child<I: Into<DerivationIndex>>(&self, index: I) -> Self {
+        let mut path = self.clone();
+        path.push(index);
+        path
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, DerivationIndex> {
+        self.0.iter()
+    }
+
+    pub fn as_slice(&self) -> &[DerivationIndex] {
+        &self.0
+    }
+}
+
+impl From<Vec<DerivationIndex>> for DerivationPath {
+    fn from(indices: Vec<DerivationIndex>) -> Self {
+        DerivationPath(indices)
+    }
+}
+
+impl From<&[DerivationIndex]> for DerivationPath {
+    fn from(indices: &[DerivationIndex]) -> Self {
+        DerivationPath(indices.to_vec())
+    }
+}
+
+impl From<DerivationPath> for Vec<DerivationIndex> {
+    fn from(path: DerivationPath) -> Self {
+        path.0
+    }
+}
+
+impl AsRef<[DerivationIndex]> for DerivationPath {
+    fn as_ref(&self) -> &[DerivationIndex] {
+        &self.0
+    }
+}
+
+impl core::iter::FromIterator<DerivationIndex> for DerivationPath {
+    fn from_iter<I: IntoIterator<Item = DerivationIndex>>(iter: I) -> Self {
+        DerivationPath(iter.into_iter().collect())
+    }
+}
+
+impl Extend<DerivationIndex> for DerivationPath {
+    fn extend<I: IntoIterator<Item = DerivationIndex>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl IntoIterator for DerivationPath {
+    type Item = DerivationIndex;
+    type IntoIter = alloc::vec::IntoIter<DerivationIndex>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DerivationPath {
+    type Item = &'a DerivationIndex;
+    type IntoIter = core::slice::Iter<'a, DerivationIndex>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+fn parse_component(
+    position: usize,
+    component: &str,
+) -> Result<DerivationIndex, DerivationPathParseError> {
+    component.parse().map_err(|error| match error {
+        DerivationIndexError::Empty => DerivationPathParseError::EmptyComponent(position),
+        DerivationIndexError::InvalidCharacter => {
+            DerivationPathParseError::InvalidComponent(position)
+        }
+        DerivationIndexError::OutOfRange => DerivationPathParseError::IndexOutOfRange(position),
+    })
+}
+
+impl FromStr for DerivationPath {
+    type Err = DerivationPathParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = match s.strip_prefix('m') {
+            Some("") => return Ok(DerivationPath::new()),
+            Some(rest) => match rest.strip_prefix('/') {
+                Some(rest) => rest,
+                None => return Err(DerivationPathParseError::InvalidComponent(0)),
+            },
+            None => s,
+        };
+        s.split('/')
+            .enumerate()
+            .map(|(position, component)| parse_component(position, component))
+            .collect()
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("m")?;
+        for index in self.iter() {
+            write!(f, "/{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DerivationPathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DerivationPathParseError::EmptyComponent(position) => {
+                write!(
+                    f,
+                    "Empty derivation path component at position {}",
+                    position
+                )
+            }
+            DerivationPathParseError::InvalidComponent(position) => {
+                write!(
+                    f,
+                    "Invalid derivation path component at position {}",
+                    position
+                )
+            }
+            DerivationPathParseError::IndexOutOfRange(position) => {
+                write!(f, "Derivation index out of range at position {}", position)
+            }
+        }
+    }
+}
+impl Error for DerivationPathParseError {}
+
+impl fmt::Display for DerivationPathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Derivation failed at position {}: {}",
+            self.position, self.error
+        )
+    }
+}
+impl Error for DerivationPathError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
@@ -0,0 +1,93 @@
+//! Public key prepared for many soft derivations
+
+use core::convert::TryInto;
+use core::fmt;
+use core::ops::Range;
+use cryptoxide::curve25519::Ge;
+
+use alloc::vec::Vec;
+
+use super::hmac::KeyedHmac;
+use super::{
+    mk_xpub, scalar_of_trunc28_mul8, serialize_index, DerivationError, DerivationIndex,
+    DerivationScheme, DerivationType, XPub, XPUB_SIZE,
+};
+
+/// `XPub` prepared for many soft derivations
+///
+/// The point of the public key is decompressed once and the HMAC-SHA512
+/// states keyed with the chain code are computed once, so that every
+/// derivation only costs the hashing of the child index, one base point
+/// multiplication and one point addition.
+#[derive(Clone)]
+pub struct PreparedXPub {
+    xpub: XPub,
+    // note: `Ge::from_bytes` gives the negation of the encoded point
+    neg_point: Ge,
+    scheme: DerivationScheme,
+    hmac: KeyedHmac,
+}
+
+impl PreparedXPub {
+    /// prepare the given `XPub`, which fails if its public key is not a valid point
+    pub fn new(xpub: &XPub, scheme: DerivationScheme) -> Result<Self, DerivationError> {
+        let neg_point =
+            Ge::from_bytes(xpub.public_key_bytes()).ok_or(DerivationError::InvalidAddition)?;
+        Ok(PreparedXPub {
+            xpub: *xpub,
+            neg_point,
+            scheme,
+            hmac: KeyedHmac::new(xpub.chain_code()),
+        })
+    }
+
+    pub fn xpub(&self) -> &XPub {
+        &self.xpub
+    }
+
+    pub fn scheme(&self) -> DerivationScheme {
+        self.scheme
+    }
+
+    /// derive the child public key at the given soft index, same as `XPub::derive`
+    pub fn derive<I: Into<DerivationIndex>>(&self, index: I) -> Result<XPub, DerivationError> {
+        let index = index.into();
+        if let DerivationType::Hard(_) = DerivationType::from_index(index) {
+            return Err(DerivationError::ExpectedSoftDerivation);
+        }
+        let pk = self.xpub.public_key_bytes();
+        let seri = serialize_index(index.raw(), self.scheme);
+
+        let mut zout = [0u8; 64];
+        self.hmac.mac(0x2, pk, &seri, &mut zout);
+        let zl: &[u8; 32] = zout[0..32].try_into().unwrap();
+
+        // left = kl + 8 * trunc28(zl), computed as -(-pk - 8 * trunc28(zl) * B)
+        let zpoint = Ge::scalarmult_base(&scalar_of_trunc28_mul8(zl, self.scheme));
+        let mut left = (&self.neg_point - &zpoint.to_cached()).to_full().to_bytes();
+        left[31] ^= 0x80;
+
+        let mut iout = [0u8; 64];
+        self.hmac.mac(0x3, pk, &seri, &mut iout);
+        let cc = &iout[32..];
+
+        let mut out = [0u8; XPUB_SIZE];
+        mk_xpub(&mut out, &left, cc);
+        Ok(XPub::from_bytes(out))
+    }
+
+    /// derive the children at every index of the given range of raw `u32`
+    /// indices, which must all be soft indices
+    pub fn derive_range(&self, range: Range<u32>) -> Result<Vec<XPub>, DerivationError> {
+        range.map(|index| self.derive(index)).collect()
+    }
+}
+
+impl fmt::Debug for PreparedXPub {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PreparedXPub")
+            .field("xpub", &self.xpub)
+            .field("scheme", &self.scheme)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,38 @@
+//! Legacy V1 derivation, as used by the Byron era Daedalus wallets.
+//!
+//! This derivation is kept only to derive existing legacy keys in order to
+//! move their funds to V2 keys. The additions are not proper BIP32-Ed25519
+//! additions (no carry, reduction modulo the group order), and derived keys
+//! do not keep the Ed25519 scalar invariants.
+
+use cryptoxide::curve25519::Scalar;
+
+pub fn add_256bits_v1(x: &[u8; 32], y: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for i in 0..32 {
+        out[i] = x[i].wrapping_add(y[i]);
+    }
+    out
+}
+
+pub fn add_28_mul8_v1(x: &[u8; 32], y: &[u8; 32]) -> [u8; 32] {
+    // each byte of y is shifted independently: the bits shifted out are lost
+    let mut yfe8 = [0u8; 32];
+    for i in 0..32 {
+        yfe8[i] = y[i] << 3;
+    }
+
+    let mut r = [0u8; 64];
+    let mut carry = 0u16;
+    for i in 0..32 {
+        let v = x[i] as u16 + yfe8[i] as u16 + carry;
+        r[i] = v as u8;
+        carry = v >> 8;
+    }
+    r[32] = carry as u8;
+    Scalar::reduce_from_wide_bytes(&r).to_bytes()
+}
+
+pub fn be32(i: u32) -> [u8; 4] {
+    [(i >> 24) as u8, (i >> 16) as u8, (i >> 8) as u8, i as u8]
+}
@@ -0,0 +1,311 @@
+//! Keys with their position in the derivation tree
+//!
+//! `ExtendedXPrv` and `ExtendedXPub` carry, like BIP32 serialized keys,
+//! the depth of the key, the fingerprint of its parent and the index it
+//! has been derived with. These fields are filled in when deriving.
+//!
+//! The binary encoding is:
+//!
+//! ```text
+//! depth (4 bytes, big endian) | parent fingerprint (4 bytes)
+//!     | child index (4 bytes, big endian) | key (96 bytes XPrv or 64 bytes XPub)
+//! ```
+//!
+//! A master key has a depth of 0, and both its parent fingerprint and
+//! child index are zeros.
+
+use core::convert::TryInto;
+use core::error::Error;
+use core::fmt;
+
+use super::derivation::{
+    DerivationError, DerivationIndex, DerivationPath, DerivationPathError, DerivationScheme,
+};
+use super::key::{
+    PrivateKeyError, PublicKeyError, XPrv, XPub, FINGERPRINT_SIZE, XPRV_SIZE, XPUB_SIZE,
+};
+
+const METADATA_SIZE: usize = 4 + FINGERPRINT_SIZE + 4;
+
+/// Size of the binary encoding of an `ExtendedXPrv`
+pub const EXTENDED_XPRV_SIZE: usize = METADATA_SIZE + XPRV_SIZE;
+
+/// Size of the binary encoding of an `ExtendedXPub`
+pub const EXTENDED_XPUB_SIZE: usize = METADATA_SIZE + XPUB_SIZE;
+
+/// Possible errors when decoding an `ExtendedXPrv` or an `ExtendedXPub`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtendedKeyError {
+    InvalidLength(usize),
+    InvalidPrivateKey(PrivateKeyError),
+    InvalidPublicKey(PublicKeyError),
+}
+
+/// position of a key in the derivation tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Metadata {
+    depth: u32,
+    parent_fingerprint: [u8; FINGERPRINT_SIZE],
+    child_index: DerivationIndex,
+}
+
+impl Metadata {
+    const MASTER: Self = Metadata {
+        depth: 0,
+        parent_fingerprint: [0; FINGERPRINT_SIZE],
+        child_index: DerivationIndex::from_raw(0),
+    };
+
+    fn child(&self, parent: &XPub, index: DerivationIndex) -> Self {
+        Metadata {
+            depth: self.depth + 1,
+            parent_fingerprint: parent.fingerprint(),
+            child_index: index,
+        }
+    }
+
+    fn write(&self, out: &mut [u8]) {
+        out[0..4].copy_from_slice(&self.depth.to_be_bytes());
+        out[4..8].copy_from_slice(&self.parent_fingerprint);
+        out[8..12].copy_from_slice(&self.child_index.raw().to_be_bytes());
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        Metadata {
+            depth: u32::from_be_bytes(bytes[0..4].try_into().unwrap()),
+            parent_fingerprint: bytes[4..8].try_into().unwrap(),
+            child_index: u32::from_be_bytes(bytes[8..12].try_into().unwrap()).into(),
+        }
+    }
+}
+
+/// `XPrv` with its depth, parent fingerprint and child index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedXPrv {
+    xprv: XPrv,
+    metadata: Metadata,
+}
+
+/// `XPub` with its depth, parent fingerprint and child index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExtendedXPub {
+    xpub: XPub,
+    metadata: Metadata,
+}
+
+impl ExtendedXPrv {
+    /// a master key, at depth 0
+    pub fn master(xprv: XPrv) -> Self {
+        ExtendedXPrv {
+            xprv,
+            metadata: Metadata::MASTER,
+        }
+    }
+
+    /// create an `ExtendedXPrv` from a key and its position in the tree
+    pub fn from_parts(
+        xprv: XPrv,
+        depth: u32,
+        parent_fingerprint: [u8; FINGERPRINT_SIZE],
+        child_index: DerivationIndex,
+    ) -> Self {
+        ExtendedXPrv {
+            xprv,
+            metadata: Metadata {
+                depth,
+                parent_fingerprint,
+                child_index,
+            },
+        }
+    }
+
+    pub fn xprv(&self) -> &XPrv {
+        &self.xprv
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.metadata.depth
+    }
+
+    /// fingerprint of the parent key, zeros for a master key
+    pub fn parent_fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        self.metadata.parent_fingerprint
+    }
+
+    /// index this key has been derived with, 0 for a master key
+    pub fn child_index(&self) -> DerivationIndex {
+        self.metadata.child_index
+    }
+
+    pub fn public(&self) -> ExtendedXPub {
+        ExtendedXPub {
+            xpub: self.xprv.public(),
+            metadata: self.metadata,
+        }
+    }
+
+    /// derive the child key at the given index, see `XPrv::derive_checked`
+    pub fn derive<I: Into<DerivationIndex>>(
+        &self,
+        scheme: DerivationScheme,
+        index: I,
+    ) -> Result<Self, DerivationError> {
+        let index = index.into();
+        let xprv = self
+            .xprv
+            .derive_checked(scheme, self.metadata.depth, index)?;
+        Ok(ExtendedXPrv {
+            xprv,
+            metadata: self.metadata.child(&self.xprv.public(), index),
+        })
+    }
+
+    /// derive every component of the given path in turn, starting from this key
+    pub fn derive_path(
+        &self,
+        scheme: DerivationScheme,
+        path: &DerivationPath,
+    ) -> Result<Self, DerivationPathError> {
+        path.iter()
+            .enumerate()
+            .try_fold(self.clone(), |xprv, (position, index)| {
+                xprv.derive(scheme, *index)
+                    .map_err(|error| DerivationPathError { position, error })
+            })
+    }
+
+    pub fn to_bytes(&self) -> [u8; EXTENDED_XPRV_SIZE] {
+        let mut out = [0u8; EXTENDED_XPRV_SIZE];
+        self.metadata.write(&mut out[..METADATA_SIZE]);
+        out[METADATA_SIZE..].copy_from_slice(self.xprv.as_ref());
+        out
+    }
+
+    /// decode an `ExtendedXPrv`, the key is checked with `XPrv::from_slice_verified`
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, ExtendedKeyError> {
+        if bytes.len() != EXTENDED_XPRV_SIZE {
+            return Err(ExtendedKeyError::InvalidLength(bytes.len()));
+        }
+        let xprv = XPrv::from_slice_verified(&bytes[METADATA_SIZE..])
+            .map_err(ExtendedKeyError::InvalidPrivateKey)?;
+        Ok(ExtendedXPrv {
+            xprv,
+            metadata: Metadata::read(&bytes[..METADATA_SIZE]),
+        })
+    }
+}
+
+impl ExtendedXPub {
+    /// a master key, at depth 0
+    pub fn master(xpub: XPub) -> Self {
+        ExtendedXPub {
+            xpub,
+            metadata: Metadata::MASTER,
+        }
+    }
+
+    /// create an `ExtendedXPub` from a key and its position in the tree
+    pub fn from_parts(
+        xpub: XPub,
+        depth: u32,
+        parent_fingerprint: [u8; FINGERPRINT_SIZE],
+        child_index: DerivationIndex,
+    ) -> Self {
+        ExtendedXPub {
+            xpub,
+            metadata: Metadata {
+                depth,
+                parent_fingerprint,
+                child_index,
+            },
+        }
+    }
+
+    pub fn xpub(&self) -> &XPub {
+        &self.xpub
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.metadata.depth
+    }
+
+    /// fingerprint of the parent key, zeros for a master key
+    pub fn parent_fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        self.metadata.parent_fingerprint
+    }
+
+    /// index this key has been derived with, 0 for a master key
+    pub fn child_index(&self) -> DerivationIndex {
+        self.metadata.child_index
+    }
+
+    /// derive the child public key at the given soft index, see `XPub::derive_checked`
+    pub fn derive<I: Into<DerivationIndex>>(
+        &self,
+        scheme: DerivationScheme,
+        index: I,
+    ) -> Result<Self, DerivationError> {
+        let index = index.into();
+        let xpub = self
+            .xpub
+            .derive_checked(scheme, self.metadata.depth, index)?;
+        Ok(ExtendedXPub {
+            xpub,
+            metadata: self.metadata.child(&self.xpub, index),
+        })
+    }
+
+    /// derive every component of the given path in turn, starting from this key
+    pub fn derive_path(
+        &self,
+        scheme: DerivationScheme,
+        path: &DerivationPath,
+    ) -> Result<Self, DerivationPathError> {
+        path.iter()
+            .enumerate()
+            .try_fold(*self, |xpub, (position, index)| {
+                xpub.derive(scheme, *index)
+                    .map_err(|error| DerivationPathError { position, error })
+            })
+    }
+
+    pub fn to_bytes(&self) -> [u8; EXTENDED_XPUB_SIZE] {
+        let mut out = [0u8; EXTENDED_XPUB_SIZE];
+        self.metadata.write(&mut out[..METADATA_SIZE]);
+        out[METADATA_SIZE..].copy_from_slice(self.xpub.as_ref());
+        out
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, ExtendedKeyError> {
+        if bytes.len() != EXTENDED_XPUB_SIZE {
+            return Err(ExtendedKeyError::InvalidLength(bytes.len()));
+        }
+        let xpub = XPub::from_slice(&bytes[METADATA_SIZE..])
+            .map_err(ExtendedKeyError::InvalidPublicKey)?;
+        Ok(ExtendedXPub {
+            xpub,
+            metadata: Metadata::read(&bytes[..METADATA_SIZE]),
+        })
+    }
+}
+
+impl fmt::Display for ExtendedKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtendedKeyError::InvalidLength(length) => {
+                write!(f, "Invalid extended key length {}", length)
+            }
+            ExtendedKeyError::InvalidPrivateKey(e) => write!(f, "Invalid private key: {}", e),
+            ExtendedKeyError::InvalidPublicKey(e) => write!(f, "Invalid public key: {}", e),
+        }
+    }
+}
+impl Error for ExtendedKeyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExtendedKeyError::InvalidPrivateKey(e) => Some(e),
+            ExtendedKeyError::InvalidPublicKey(e) => Some(e),
+            ExtendedKeyError::InvalidLength(_) => None,
+        }
+    }
+}
@@ -1,4 +1,20 @@
-const ALPHABET: &'static [u8] = b"0123456789abcdef";
+use core::error::Error;
+use core::fmt;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::securemem;
+
+const ALPHABET: &[u8] = b"0123456789abcdef";
+
+/// Possible errors when decoding an hexadecimal string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    /// invalid character at the given position
+    InvalidCharacter(usize),
+    OddLength,
+}
 
 pub fn encode(input: &[u8]) -> String {
     let mut v = Vec::with_capacity(input.len() * 2);
@@ -9,3 +25,47 @@ pub fn encode(input: &[u8]) -> String {
 
     unsafe { String::from_utf8_unchecked(v) }
 }
+
+fn decode_nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// decode an hexadecimal string, in lowercase or uppercase
+///
+/// The string may encode a secret key, so the bytes decoded before an
+/// invalid character are wiped before returning the error.
+pub fn decode(input: &str) -> Result<Vec<u8>, HexError> {
+    let pairs = input.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(HexError::OddLength);
+    }
+    let mut v = Vec::with_capacity(input.len() / 2);
+    for (i, pair) in pairs.enumerate() {
+        match (decode_nibble(pair[0]), decode_nibble(pair[1])) {
+            (Some(hi), Some(lo)) => v.push((hi << 4) | lo),
+            (hi, _) => {
+                securemem::zero(&mut v);
+                let position = if hi.is_none() { i * 2 } else { i * 2 + 1 };
+                return Err(HexError::InvalidCharacter(position));
+            }
+        }
+    }
+    Ok(v)
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HexError::InvalidCharacter(position) => {
+                write!(f, "Invalid hexadecimal character at position {}", position)
+            }
+            HexError::OddLength => f.write_str("Odd number of hexadecimal characters"),
+        }
+    }
+}
+impl Error for HexError {}
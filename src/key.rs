@@ -1,18 +1,39 @@
-use std::fmt;
+use core::fmt;
 
 use cryptoxide::constant_time::CtEqual;
+use cryptoxide::curve25519::{Ge, Scalar};
 use cryptoxide::ed25519;
-use cryptoxide::ed25519::signature_extended;
+use cryptoxide::hashing::blake2b_224;
 use cryptoxide::hashing::sha2::Sha512;
-
-use std::convert::{TryFrom, TryInto};
-use std::error::Error;
-use std::hash::{Hash, Hasher};
-
-use super::derivation::{self, DerivationError, DerivationIndex, DerivationScheme};
-use super::hex;
+#[cfg(feature = "bip39")]
+use cryptoxide::{
+    hmac::Hmac,
+    mac::Mac,
+    pbkdf2::pbkdf2,
+    sha2::{Sha256 as Sha256Digest, Sha512 as Sha512Digest},
+};
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use core::convert::{TryFrom, TryInto};
+use core::error::Error;
+use core::hash::{Hash, Hasher};
+use core::ops::Range;
+use core::str::FromStr;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "bip39")]
+use super::bip39::{Bip39Error, Entropy, Seed, MAX_ENTROPY_SIZE};
+use super::derivation::{
+    self, DerivationError, DerivationIndex, DerivationPath, DerivationPathError, DerivationScheme,
+};
+use super::hex::{self, HexError};
+use super::point;
+use super::scalar;
 use super::securemem;
-use super::signature::Signature;
+use super::signature::{Signature, SIGNATURE_SIZE};
 
 /// Extended Private key size in bytes
 pub const XPRV_SIZE: usize = 96;
@@ -23,28 +44,49 @@ pub const XPUB_SIZE: usize = 64;
 pub const PUBLIC_KEY_SIZE: usize = 32;
 pub const CHAIN_CODE_SIZE: usize = 32;
 
+/// Key identifier size in bytes
+pub const IDENTIFIER_SIZE: usize = 28;
+
+/// Key fingerprint size in bytes
+pub const FINGERPRINT_SIZE: usize = 4;
+
 /// Possible errors during conversion from bytes
 ///
 /// HighestBitsInvalid and LowestBitsInvalid are errors
 /// reported linked to the shape of a normal extended ed25519 key.
 ///
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PrivateKeyError {
     LengthInvalid(usize),
     HighestBitsInvalid,
     LowestBitsInvalid,
+    HexInvalid(HexError),
 }
 
 /// Possible errors during conversion from bytes
-#[derive(Debug)]
+///
+/// The Point errors are only reported by `XPub::from_bytes_verified` and
+/// `XPub::from_slice_verified`, which check the public key.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PublicKeyError {
     LengthInvalid(usize),
+    HexInvalid(HexError),
+    /// the public key is not the canonical encoding of a point of the curve
+    PointInvalid,
+    /// the public key is a point of small order, e.g. the identity
+    PointSmallOrder,
+    /// the public key is not in the prime order subgroup
+    PointMixedTorsion,
 }
 
 /// HDWallet extended private key
 ///
 /// Effectively this is an ed25519 extended secret key (64 bytes) followed by a chain code (32 bytes).
 ///
+/// The layout is the one of its bytes, so that a page holding them can be
+/// read as an `XPrv`.
+#[repr(transparent)]
 pub struct XPrv([u8; XPRV_SIZE]);
 impl XPrv {
     /// takes the given raw bytes and perform some modifications to normalize
@@ -55,7 +97,9 @@ impl XPrv {
         bytes[31] &= 0b0001_1111;
         bytes[31] |= 0b0100_0000;
 
-        Self::from_bytes(bytes)
+        let xprv = Self::from_bytes(bytes);
+        securemem::zero(&mut bytes);
+        xprv
     }
 
     /// Takes the given raw bytes and perform some modifications to normalize
@@ -66,7 +110,9 @@ impl XPrv {
         bytes[31] &= 0b0011_1111;
         bytes[31] |= 0b0100_0000;
 
-        Self::from_bytes(bytes)
+        let xprv = Self::from_bytes(bytes);
+        securemem::zero(&mut bytes);
+        xprv
     }
 
     /// Check if the 3rd highest bit is clear as expected from the paper
@@ -84,10 +130,10 @@ impl XPrv {
     /// Ed25519 signature system make extended key, but *also* force clear the 3rd highest bit of the key
     /// instead of returning an error
     pub fn from_nonextended_force(bytes: &[u8; 32], chain_code: &[u8; CHAIN_CODE_SIZE]) -> Self {
-        let mut extended_out = [0u8; XPRV_SIZE];
-        extended_out[0..64].copy_from_slice(&Sha512::new().update(bytes).finalize());
-        extended_out[64..96].clone_from_slice(chain_code);
-        Self::normalize_bytes_force3rd(extended_out)
+        let mut extended_out = nonextended_to_extended(bytes, chain_code);
+        let xprv = Self::normalize_bytes_force3rd(extended_out);
+        securemem::zero(&mut extended_out);
+        xprv
     }
 
     /// Takes a non-extended Ed25519 secret key and hash through SHA512 it in the same way the standard
@@ -96,14 +142,14 @@ impl XPrv {
     /// bip32-ed25519 paper:
     ///
     /// > "2) We admit only those ~k such that the third highest bit of the last byte of kL is zero."
+    #[allow(clippy::result_unit_err)]
     pub fn from_nonextended_noforce(
         bytes: &[u8; 32],
         chain_code: &[u8; CHAIN_CODE_SIZE],
     ) -> Result<Self, ()> {
-        let mut extended_out = [0u8; XPRV_SIZE];
-        extended_out[0..64].copy_from_slice(&Sha512::new().update(bytes).finalize());
-        extended_out[64..96].clone_from_slice(chain_code);
+        let mut extended_out = nonextended_to_extended(bytes, chain_code);
         let xprv = Self::normalize_bytes_ed25519(extended_out);
+        securemem::zero(&mut extended_out);
         if xprv.is_3rd_highest_bit_clear() {
             Ok(xprv)
         } else {
@@ -111,6 +157,103 @@ impl XPrv {
         }
     }
 
+    /// Create the root `XPrv` of a BIP39 entropy using the Icarus master key generation,
+    /// as used by cardano-wallet, Daedalus and Yoroi (CIP-3).
+    ///
+    /// The entropy is stretched with PBKDF2-HMAC-SHA512 (4096 iterations) using
+    /// the passphrase as password, and then normalized with `normalize_bytes_force3rd`.
+    /// The passphrase may be empty, it is used as is and should already be normalized
+    /// by the caller.
+    #[cfg(feature = "bip39")]
+    pub fn from_entropy_icarus(entropy: &Entropy, passphrase: &[u8]) -> Self {
+        Self::icarus_stretch(entropy.as_ref(), passphrase)
+    }
+
+    #[cfg(feature = "bip39")]
+    fn icarus_stretch(salt: &[u8], passphrase: &[u8]) -> Self {
+        let mut mac = Hmac::new(Sha512Digest::new(), passphrase);
+        let mut out = [0u8; XPRV_SIZE];
+        pbkdf2(&mut mac, salt, 4096, &mut out);
+        let xprv = Self::normalize_bytes_force3rd(out);
+        securemem::zero(&mut out);
+        xprv
+    }
+
+    /// Create the root `XPrv` of a BIP39 mnemonic phrase using the Icarus master key generation.
+    ///
+    /// see `from_entropy_icarus`
+    #[cfg(feature = "bip39")]
+    pub fn from_mnemonic_icarus(phrase: &str, passphrase: &[u8]) -> Result<Self, Bip39Error> {
+        let entropy = Entropy::from_mnemonic(phrase)?;
+        Ok(Self::from_entropy_icarus(&entropy, passphrase))
+    }
+
+    /// Create the root `XPrv` of a BIP39 entropy using the Trezor master key generation.
+    ///
+    /// Trezor uses the Icarus master key generation, except for 24 words mnemonic
+    /// phrases where the firmware feeds the checksum byte along with the entropy
+    /// to PBKDF2. For any other entropy size, this is the same as `from_entropy_icarus`.
+    #[cfg(feature = "bip39")]
+    pub fn from_entropy_trezor(entropy: &Entropy, passphrase: &[u8]) -> Self {
+        if entropy.as_ref().len() != MAX_ENTROPY_SIZE {
+            return Self::from_entropy_icarus(entropy, passphrase);
+        }
+        let mut salt = [0u8; MAX_ENTROPY_SIZE + 1];
+        salt[..MAX_ENTROPY_SIZE].copy_from_slice(entropy.as_ref());
+        salt[MAX_ENTROPY_SIZE] = entropy.checksum();
+        let xprv = Self::icarus_stretch(&salt, passphrase);
+        securemem::zero(&mut salt);
+        xprv
+    }
+
+    /// Create the root `XPrv` of a BIP39 mnemonic phrase using the Trezor master key generation.
+    ///
+    /// see `from_entropy_trezor`
+    #[cfg(feature = "bip39")]
+    pub fn from_mnemonic_trezor(phrase: &str, passphrase: &[u8]) -> Result<Self, Bip39Error> {
+        let entropy = Entropy::from_mnemonic(phrase)?;
+        Ok(Self::from_entropy_trezor(&entropy, passphrase))
+    }
+
+    /// Create the root `XPrv` of a BIP39 seed using the Ledger master key generation,
+    /// as implemented by the Ledger Cardano application (CIP-3).
+    ///
+    /// The extended secret key is HMAC-SHA512 of the seed keyed with `"ed25519 seed"`,
+    /// hashed again until the 3rd highest bit is clear, and the chain code is
+    /// HMAC-SHA256 of `0x01 || seed` with the same key.
+    #[cfg(feature = "bip39")]
+    pub fn from_ledger_seed(seed: &Seed) -> Self {
+        const KEY: &[u8] = b"ed25519 seed";
+
+        let mut out = [0u8; XPRV_SIZE];
+        let mut hmac = Hmac::new(Sha512Digest::new(), KEY);
+        hmac.input(seed.as_ref());
+        hmac.raw_result(&mut out[0..64]);
+        while !Self::from_bytes(out).is_3rd_highest_bit_clear() {
+            hmac.reset();
+            hmac.input(&out[0..64]);
+            hmac.raw_result(&mut out[0..64]);
+        }
+
+        let mut hmac = Hmac::new(Sha256Digest::new(), KEY);
+        hmac.input(&[0x01]);
+        hmac.input(seed.as_ref());
+        hmac.raw_result(&mut out[64..96]);
+
+        let xprv = Self::normalize_bytes_ed25519(out);
+        securemem::zero(&mut out);
+        xprv
+    }
+
+    /// Create the root `XPrv` of a BIP39 mnemonic phrase using the Ledger master key generation.
+    ///
+    /// see `Entropy::to_seed` and `from_ledger_seed`
+    #[cfg(feature = "bip39")]
+    pub fn from_mnemonic_ledger(phrase: &str, passphrase: &[u8]) -> Result<Self, Bip39Error> {
+        let entropy = Entropy::from_mnemonic(phrase)?;
+        Ok(Self::from_ledger_seed(&entropy.to_seed(passphrase)))
+    }
+
     /// create a `XPrv` by its components (a 64 bytes extended secret key, and a 32 bytes chain code)
     ///
     /// No verification is done on the extended secret key
@@ -121,7 +264,9 @@ impl XPrv {
         let mut buf = [0u8; XPRV_SIZE];
         buf[0..64].copy_from_slice(sk);
         buf[64..96].copy_from_slice(chain_code);
-        Self::from_bytes(buf)
+        let xprv = Self::from_bytes(buf);
+        securemem::zero(&mut buf);
+        xprv
     }
 
     // Create a XPrv from the given bytes.
@@ -140,19 +285,20 @@ impl XPrv {
     /// This function allow the 3rd highest bit to not be clear (to handle potential derived valid xprv),
     /// but self.is_3rd_highest_bit_clear() can be called to check if the 3rd highest bit
     /// is assumed to be clear or not.
-    pub fn from_bytes_verified(bytes: [u8; XPRV_SIZE]) -> Result<Self, PrivateKeyError> {
+    pub fn from_bytes_verified(mut bytes: [u8; XPRV_SIZE]) -> Result<Self, PrivateKeyError> {
         let scalar = &bytes[0..32];
         let last = scalar[31];
         let first = scalar[0];
 
-        if (last & 0b1100_0000) != 0b0100_0000 {
-            return Err(PrivateKeyError::HighestBitsInvalid);
-        }
-        if (first & 0b0000_0111) != 0b0000_0000 {
-            return Err(PrivateKeyError::LowestBitsInvalid);
-        }
-
-        Ok(XPrv(bytes))
+        let error = if (last & 0b1100_0000) != 0b0100_0000 {
+            PrivateKeyError::HighestBitsInvalid
+        } else if (first & 0b0000_0111) != 0b0000_0000 {
+            PrivateKeyError::LowestBitsInvalid
+        } else {
+            return Ok(XPrv(bytes));
+        };
+        securemem::zero(&mut bytes);
+        Err(error)
     }
 
     pub fn from_slice_verified(bytes: &[u8]) -> Result<Self, PrivateKeyError> {
@@ -162,7 +308,9 @@ impl XPrv {
 
         let mut buf = [0u8; XPRV_SIZE];
         buf[..].clone_from_slice(bytes);
-        XPrv::from_bytes_verified(buf)
+        let xprv = XPrv::from_bytes_verified(buf);
+        securemem::zero(&mut buf);
+        xprv
     }
 
     /// Create a `XPrv` from the given slice. This slice must be of size `XPRV_SIZE`
@@ -174,7 +322,9 @@ impl XPrv {
         }
         let mut buf = [0u8; XPRV_SIZE];
         buf[..].clone_from_slice(bytes);
-        Ok(XPrv::from_bytes(buf))
+        let xprv = XPrv::from_bytes(buf);
+        securemem::zero(&mut buf);
+        Ok(xprv)
     }
 
     /// Get the associated `XPub`
@@ -187,11 +337,59 @@ impl XPrv {
         XPub::from_bytes(out)
     }
 
+    /// identifier of the key, see `XPub::identifier`
+    pub fn identifier(&self) -> [u8; IDENTIFIER_SIZE] {
+        self.public().identifier()
+    }
+
+    /// fingerprint of the key, see `XPub::fingerprint`
+    pub fn fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        self.public().fingerprint()
+    }
+
     /// sign the given message with the `XPrv`.
     ///
+    /// The nonce, the hashing context that absorbed the secret key and the
+    /// intermediate scalars are wiped before returning.
     pub fn sign<T>(&self, message: &[u8]) -> Signature<T> {
-        let extended_key = <&[u8; 64]>::try_from(&self.0[0..64]).unwrap();
-        Signature::from_bytes(signature_extended(message, extended_key))
+        Signature::from_bytes(self.sign_with_dom(&[], message))
+    }
+
+    // Ed25519 signature with the extended secret key, as `signature_extended`
+    // of cryptoxide, with both hashes prefixed by `dom`: empty for pure
+    // Ed25519, `dom2` of RFC 8032 for Ed25519ctx and Ed25519ph. The secret
+    // intermediates are wiped once the signature is computed.
+    pub(crate) fn sign_with_dom(&self, dom: &[u8], message: &[u8]) -> [u8; SIGNATURE_SIZE] {
+        let kl = <&[u8; 32]>::try_from(&self.0[0..32]).unwrap();
+        let kr = &self.0[32..64];
+        let xpub = self.public();
+
+        let mut ctx = Sha512::new();
+        ctx.update_mut(dom);
+        ctx.update_mut(kr);
+        ctx.update_mut(message);
+        let mut nonce_hash = ctx.finalize_reset();
+        securemem::overwrite(&mut ctx, Sha512::new());
+        let mut nonce_scalar = Scalar::reduce_from_wide_bytes(&nonce_hash);
+        let mut nonce = nonce_scalar.to_bytes();
+        let r = Ge::scalarmult_base(&nonce_scalar).to_bytes();
+
+        let hash = Sha512::new()
+            .update(dom)
+            .update(&r)
+            .update(xpub.public_key_bytes())
+            .update(message)
+            .finalize();
+        let k = Scalar::reduce_from_wide_bytes(&hash);
+        let s = scalar::muladd(&k.to_bytes(), kl, &nonce);
+
+        let mut signature = [0u8; SIGNATURE_SIZE];
+        signature[0..32].copy_from_slice(&r);
+        signature[32..64].copy_from_slice(&s.to_bytes());
+        securemem::zero(&mut nonce_hash);
+        securemem::zero(&mut nonce);
+        securemem::overwrite(&mut nonce_scalar, Scalar::from_bytes(&[0; 32]));
+        signature
     }
 
     /// verify a given signature
@@ -201,8 +399,63 @@ impl XPrv {
         xpub.verify(message, signature)
     }
 
-    pub fn derive(&self, scheme: DerivationScheme, index: DerivationIndex) -> Self {
-        derivation::private(self, index, scheme)
+    /// derive the child key at the given index
+    ///
+    /// the index can be given as a `DerivationIndex` or as its raw `u32` value
+    pub fn derive<I: Into<DerivationIndex>>(&self, scheme: DerivationScheme, index: I) -> Self {
+        derivation::private(self, index.into(), scheme)
+    }
+
+    /// derive the children at every index of the given range of raw `u32` indices
+    ///
+    /// This is faster than calling `derive` for every index: the HMAC keys
+    /// are set up once, and the public key of this key is computed once for
+    /// all the soft indices.
+    pub fn derive_range(&self, scheme: DerivationScheme, range: Range<u32>) -> Vec<Self> {
+        let mut parent = derivation::PrivateParent::new(self, scheme);
+        range.map(|index| parent.derive(index.into())).collect()
+    }
+
+    /// derive the child key at the given index, this key being at the given
+    /// depth (0 for a master key)
+    ///
+    /// Unlike `derive`, this refuses to derive beyond `MAX_DERIVATION_DEPTH`
+    /// and checks that the private scalar of the child is valid.
+    ///
+    /// An `XPrv` doesn't know its own depth, so the check is only as good as
+    /// the depth given by the caller. `ExtendedXPrv` keeps track of it.
+    pub fn derive_checked<I: Into<DerivationIndex>>(
+        &self,
+        scheme: DerivationScheme,
+        depth: u32,
+        index: I,
+    ) -> Result<Self, DerivationError> {
+        derivation::private_checked(self, depth, index.into(), scheme)
+    }
+
+    /// derive every component of the given path in turn, starting from this key
+    ///
+    /// An empty path returns a copy of this key. Like `derive`, neither the
+    /// depth nor the scalars are checked, see `derive_path_checked`.
+    pub fn derive_path(&self, scheme: DerivationScheme, path: &DerivationPath) -> Self {
+        path.iter()
+            .fold(self.clone(), |xprv, index| xprv.derive(scheme, *index))
+    }
+
+    /// derive every component of the given path in turn with `derive_checked`,
+    /// this key being at the given depth (0 for a master key)
+    pub fn derive_path_checked(
+        &self,
+        scheme: DerivationScheme,
+        depth: u32,
+        path: &DerivationPath,
+    ) -> Result<Self, DerivationPathError> {
+        path.iter()
+            .enumerate()
+            .try_fold(self.clone(), |xprv, (position, index)| {
+                xprv.derive_checked(scheme, depth.saturating_add(position as u32), *index)
+                    .map_err(|error| DerivationPathError { position, error })
+            })
     }
 
     pub fn get_extended_mut(&self, out: &mut [u8; EXTENDED_SECRET_KEY_SIZE]) {
@@ -225,6 +478,14 @@ impl XPrv {
         &self.0[64..96]
     }
 
+    /// hexadecimal representation of the whole `XPrv`, secret key included
+    ///
+    /// The `Debug` and `Display` implementations don't display the secret key,
+    /// this has to be used explicitly to export it (see also `FromStr`).
+    pub fn to_hex_secret(&self) -> String {
+        hex::encode(self.as_ref())
+    }
+
     pub fn extended_secret_key(&self) -> [u8; EXTENDED_SECRET_KEY_SIZE] {
         let mut buf = [0u8; EXTENDED_SECRET_KEY_SIZE];
         buf.copy_from_slice(self.extended_secret_key_slice());
@@ -242,14 +503,37 @@ impl Clone for XPrv {
         Self::from_slice(self.as_ref()).expect("it is already a safely constructed XPrv")
     }
 }
+/// Only the public key is displayed, the secret key and the chain code are redacted.
 impl fmt::Debug for XPrv {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", hex::encode(self.as_ref()))
+        let xpub = self.public();
+        f.debug_struct("XPrv")
+            .field("fingerprint", &hex::encode(&xpub.fingerprint()))
+            .field("public_key", &hex::encode(xpub.public_key_slice()))
+            .finish_non_exhaustive()
     }
 }
+/// Only the public key is displayed, the secret key and the chain code are redacted.
+///
+/// Use `XPrv::to_hex_secret` to get the hexadecimal representation of the whole key.
 impl fmt::Display for XPrv {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", hex::encode(self.as_ref()))
+        write!(
+            f,
+            "XPrv(public_key: {}, ..)",
+            hex::encode(self.public().public_key_slice())
+        )
+    }
+}
+impl FromStr for XPrv {
+    type Err = PrivateKeyError;
+
+    /// parse an hexadecimal `XPrv`, checked with `XPrv::from_slice_verified`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = hex::decode(s).map_err(PrivateKeyError::HexInvalid)?;
+        let xprv = XPrv::from_slice_verified(&bytes);
+        securemem::zero(&mut bytes);
+        xprv
     }
 }
 impl AsRef<[u8]> for XPrv {
@@ -268,6 +552,24 @@ impl Drop for XPrv {
     }
 }
 
+/// wipe the key, leaving an all zero (and invalid) key behind
+#[cfg(feature = "zeroize")]
+impl Zeroize for XPrv {
+    fn zeroize(&mut self) {
+        securemem::zero(&mut self.0);
+    }
+}
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for XPrv {}
+
+/// same as `From<XPrv> for [u8; XPRV_SIZE]`, but the bytes are wiped when dropped
+#[cfg(feature = "zeroize")]
+impl From<XPrv> for Zeroizing<[u8; XPRV_SIZE]> {
+    fn from(v: XPrv) -> Zeroizing<[u8; XPRV_SIZE]> {
+        Zeroizing::new(v.0)
+    }
+}
+
 /// Extended Public Key (Point + ChainCode)
 #[derive(Clone, Copy)]
 pub struct XPub([u8; XPUB_SIZE]);
@@ -300,8 +602,54 @@ impl XPub {
         Ok(Self::from_bytes(buf))
     }
 
+    /// create a `XPub` from the given bytes, checking that the public key is
+    /// a valid point which is not of small order.
+    ///
+    /// This is the constructor to use for keys received from untrusted
+    /// parties. Use `XPub::from_bytes_verified_prime_order` to also reject
+    /// the points that have a torsion component.
+    pub fn from_bytes_verified(bytes: [u8; XPUB_SIZE]) -> Result<Self, PublicKeyError> {
+        let xpub = XPub(bytes);
+        xpub.check_point(false)?;
+        Ok(xpub)
+    }
+
+    /// same as `XPub::from_bytes_verified`, also checking that the public key
+    /// is in the prime order subgroup.
+    pub fn from_bytes_verified_prime_order(bytes: [u8; XPUB_SIZE]) -> Result<Self, PublicKeyError> {
+        let xpub = XPub(bytes);
+        xpub.check_point(true)?;
+        Ok(xpub)
+    }
+
+    /// same as `XPub::from_bytes_verified`, from a slice of size `XPUB_SIZE`
+    pub fn from_slice_verified(bytes: &[u8]) -> Result<Self, PublicKeyError> {
+        Self::from_bytes_verified(Self::from_slice(bytes)?.0)
+    }
+
+    /// same as `XPub::from_bytes_verified_prime_order`, from a slice of size `XPUB_SIZE`
+    pub fn from_slice_verified_prime_order(bytes: &[u8]) -> Result<Self, PublicKeyError> {
+        Self::from_bytes_verified_prime_order(Self::from_slice(bytes)?.0)
+    }
+
+    fn check_point(&self, prime_order: bool) -> Result<(), PublicKeyError> {
+        let point =
+            point::decode_canonical(self.public_key_bytes()).ok_or(PublicKeyError::PointInvalid)?;
+        if point::is_small_order(&point) {
+            return Err(PublicKeyError::PointSmallOrder);
+        }
+        if prime_order && !point::is_torsion_free(&point) {
+            return Err(PublicKeyError::PointMixedTorsion);
+        }
+        Ok(())
+    }
+
     /// verify a signature
     ///
+    /// This uses the rules of cryptoxide, which are close to
+    /// `VerificationMode::Strict` but accept `A` and `R` of small order
+    /// (except an all zero `A`) and non canonical encodings of `A`.
+    /// Use `XPub::verify_with_mode` to pick the rules.
     pub fn verify<T>(&self, message: &[u8], signature: &Signature<T>) -> bool {
         ed25519::verify(
             message,
@@ -310,12 +658,75 @@ impl XPub {
         )
     }
 
-    pub fn derive(
+    /// derive the child public key at the given index, which has to be a soft index
+    ///
+    /// the index can be given as a `DerivationIndex` or as its raw `u32` value
+    pub fn derive<I: Into<DerivationIndex>>(
         &self,
         scheme: DerivationScheme,
-        index: DerivationIndex,
+        index: I,
     ) -> Result<Self, DerivationError> {
-        derivation::public(self, index, scheme)
+        derivation::public(self, index.into(), scheme)
+    }
+
+    /// derive the children at every index of the given range of raw `u32`
+    /// indices, which must all be soft indices
+    ///
+    /// This is faster than calling `derive` for every index, see `PreparedXPub`.
+    pub fn derive_range(
+        &self,
+        scheme: DerivationScheme,
+        range: Range<u32>,
+    ) -> Result<Vec<Self>, DerivationError> {
+        derivation::PreparedXPub::new(self, scheme)?.derive_range(range)
+    }
+
+    /// derive the child public key at the given soft index, this key being
+    /// at the given depth (0 for a master key)
+    ///
+    /// Unlike `derive`, this refuses to derive beyond `MAX_DERIVATION_DEPTH`.
+    /// As with `XPrv::derive_checked`, the depth is the one given by the
+    /// caller, `ExtendedXPub` keeps track of it.
+    pub fn derive_checked<I: Into<DerivationIndex>>(
+        &self,
+        scheme: DerivationScheme,
+        depth: u32,
+        index: I,
+    ) -> Result<Self, DerivationError> {
+        derivation::public_checked(self, depth, index.into(), scheme)
+    }
+
+    /// derive every component of the given path in turn, starting from this key
+    ///
+    /// On failure, the error reports the position in the path of the
+    /// component that could not be derived (e.g. a hardened index).
+    pub fn derive_path(
+        &self,
+        scheme: DerivationScheme,
+        path: &DerivationPath,
+    ) -> Result<Self, DerivationPathError> {
+        path.iter()
+            .enumerate()
+            .try_fold(*self, |xpub, (position, index)| {
+                xpub.derive(scheme, *index)
+                    .map_err(|error| DerivationPathError { position, error })
+            })
+    }
+
+    /// derive every component of the given path in turn with `derive_checked`,
+    /// this key being at the given depth (0 for a master key)
+    pub fn derive_path_checked(
+        &self,
+        scheme: DerivationScheme,
+        depth: u32,
+        path: &DerivationPath,
+    ) -> Result<Self, DerivationPathError> {
+        path.iter()
+            .enumerate()
+            .try_fold(*self, |xpub, (position, index)| {
+                xpub.derive_checked(scheme, depth.saturating_add(position as u32), *index)
+                    .map_err(|error| DerivationPathError { position, error })
+            })
     }
 
     pub fn get_without_chaincode(&self, out: &mut [u8; 32]) {
@@ -343,6 +754,21 @@ impl XPub {
     pub fn chain_code(&self) -> &[u8; CHAIN_CODE_SIZE] {
         (&self.0[32..64]).try_into().unwrap()
     }
+
+    /// identifier of the key: the Blake2b-224 hash of the public key,
+    /// as used for Cardano key hashes.
+    ///
+    /// The chain code is not part of the identifier.
+    pub fn identifier(&self) -> [u8; IDENTIFIER_SIZE] {
+        blake2b_224(self.public_key_bytes())
+    }
+
+    /// fingerprint of the key: the first 4 bytes of its identifier
+    pub fn fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        let mut fingerprint = [0u8; FINGERPRINT_SIZE];
+        fingerprint.copy_from_slice(&self.identifier()[..FINGERPRINT_SIZE]);
+        fingerprint
+    }
 }
 impl PartialEq for XPub {
     fn eq(&self, rhs: &XPub) -> bool {
@@ -365,6 +791,14 @@ impl fmt::Debug for XPub {
         write!(f, "{}", hex::encode(self.as_ref()))
     }
 }
+impl FromStr for XPub {
+    type Err = PublicKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(PublicKeyError::HexInvalid)?;
+        XPub::from_slice(&bytes)
+    }
+}
 impl AsRef<[u8]> for XPub {
     fn as_ref(&self) -> &[u8] {
         &self.0
@@ -384,6 +818,12 @@ impl fmt::Display for PublicKeyError {
                 "Invalid public key length, expected {} but received {}",
                 XPUB_SIZE, length
             ),
+            PublicKeyError::HexInvalid(e) => write!(f, "Invalid hexadecimal public key: {}", e),
+            PublicKeyError::PointInvalid => f.write_str("Invalid public key point"),
+            PublicKeyError::PointSmallOrder => f.write_str("Public key point of small order"),
+            PublicKeyError::PointMixedTorsion => {
+                f.write_str("Public key point not in the prime order subgroup")
+            }
         }
     }
 }
@@ -399,6 +839,7 @@ impl fmt::Display for PrivateKeyError {
             ),
             PrivateKeyError::HighestBitsInvalid => f.write_str("Invalid highest bits"),
             PrivateKeyError::LowestBitsInvalid => f.write_str("Invalid lowest bits"),
+            PrivateKeyError::HexInvalid(e) => write!(f, "Invalid hexadecimal private key: {}", e),
         }
     }
 }
@@ -425,3 +866,21 @@ pub(crate) fn mk_xpub(out: &mut [u8; XPUB_SIZE], pk: &[u8], cc: &[u8]) {
 pub fn mk_public_key(extended_secret: &[u8; 64]) -> [u8; PUBLIC_KEY_SIZE] {
     ed25519::extended_to_public(extended_secret)
 }
+
+// the standard Ed25519 extended secret key (SHA512 of the secret key), not
+// normalized yet, followed by the chain code
+fn nonextended_to_extended(
+    secret: &[u8; 32],
+    chain_code: &[u8; CHAIN_CODE_SIZE],
+) -> [u8; XPRV_SIZE] {
+    let mut context = Sha512::new();
+    context.update_mut(secret);
+    let mut hash = context.finalize_reset();
+    securemem::overwrite(&mut context, Sha512::new());
+
+    let mut out = [0u8; XPRV_SIZE];
+    out[0..64].copy_from_slice(&hash);
+    out[64..96].clone_from_slice(chain_code);
+    securemem::zero(&mut hash);
+    out
+}
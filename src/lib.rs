@@ -9,18 +9,37 @@
 //!
 //! In soft derivation, the important property is that given the parent public key,
 //! one can derive all softly derived children public key.
+//!
+//! The crate is `no_std` and only needs `alloc`, unless the `std` feature is enabled.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![cfg_attr(feature = "with-bench", feature(test))]
 
+extern crate alloc;
+
 #[cfg(test)]
 #[cfg(feature = "with-bench")]
 extern crate test;
 
+mod batch;
+mod bech32;
+#[cfg(feature = "bip39")]
+mod bip39;
 mod derivation;
+mod extended;
 mod hex;
 mod key;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod password_encryption;
+mod point;
+mod scalar;
 mod securemem;
+#[cfg(feature = "serde")]
+mod serialization;
 mod signature;
+mod variants;
+mod verification;
 
 #[cfg(test)]
 mod tests;
@@ -29,6 +48,29 @@ mod tests;
 #[cfg(feature = "with-bench")]
 mod bench;
 
-pub use derivation::{DerivationError, DerivationIndex, DerivationScheme};
-pub use key::{PrivateKeyError, PublicKeyError, XPrv, XPub, XPRV_SIZE, XPUB_SIZE};
+pub use batch::{verify_batch, BatchVerificationError};
+pub use bech32::{hrp, Bech32Error, Hrp, SignatureHrp};
+#[cfg(feature = "bip39")]
+pub use bip39::{Bip39Error, Entropy, Seed, MAX_ENTROPY_SIZE, SEED_SIZE};
+pub use derivation::{
+    DerivationError, DerivationIndex, DerivationIndexError, DerivationPath, DerivationPathError,
+    DerivationPathParseError, DerivationScheme, PreparedXPub, MAX_DERIVATION_DEPTH,
+};
+pub use extended::{
+    ExtendedKeyError, ExtendedXPrv, ExtendedXPub, EXTENDED_XPRV_SIZE, EXTENDED_XPUB_SIZE,
+};
+pub use hex::HexError;
+pub use key::{
+    PrivateKeyError, PublicKeyError, XPrv, XPub, FINGERPRINT_SIZE, IDENTIFIER_SIZE, XPRV_SIZE,
+    XPUB_SIZE,
+};
+pub use password_encryption::{
+    PasswordEncryptionError, ENCRYPTED_XPRV_SIZE, PASSWORD_NONCE_SIZE, PASSWORD_SALT_SIZE,
+};
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+pub use securemem::SecureXPrv;
 pub use signature::{Signature, SignatureError, SIGNATURE_SIZE};
+pub use variants::{
+    ContextError, ContextSignature, Prehash, PrehashSignature, MAX_CONTEXT_SIZE, PREHASH_SIZE,
+};
+pub use verification::VerificationMode;
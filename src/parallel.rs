@@ -0,0 +1,41 @@
+//! Parallel range derivation with rayon
+//!
+//! The children are returned in the order of their indices, and are the
+//! same as the ones returned by the sequential `derive_range`.
+
+use core::ops::Range;
+
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+use super::derivation::{DerivationError, DerivationScheme, PreparedXPub, PrivateParent};
+use super::key::{XPrv, XPub};
+
+impl XPrv {
+    /// same as `XPrv::derive_range`, deriving the children in parallel
+    pub fn par_derive_range(&self, scheme: DerivationScheme, range: Range<u32>) -> Vec<Self> {
+        range
+            .into_par_iter()
+            .map_init(
+                || PrivateParent::new(self, scheme),
+                |parent, index| parent.derive(index.into()),
+            )
+            .collect()
+    }
+}
+
+impl XPub {
+    /// same as `XPub::derive_range`, deriving the children in parallel
+    pub fn par_derive_range(
+        &self,
+        scheme: DerivationScheme,
+        range: Range<u32>,
+    ) -> Result<Vec<Self>, DerivationError> {
+        let prepared = PreparedXPub::new(self, scheme)?;
+        range
+            .into_par_iter()
+            .map(|index| prepared.derive(index))
+            .collect()
+    }
+}
@@ -0,0 +1,237 @@
+//! Password based encryption of `XPrv`, as used by cardano-wallet and Daedalus
+//! to store root keys at rest (EMIP-3).
+//!
+//! The encryption key is derived from the password with PBKDF2-HMAC-SHA512
+//! (19162 iterations) and the given salt, then the 96 bytes of the `XPrv` are
+//! encrypted with ChaCha20-Poly1305 (no additional data). The encrypted form is:
+//!
+//! ```text
+//! salt (32 bytes) | nonce (12 bytes) | tag (16 bytes) | encrypted xprv (96 bytes)
+//! ```
+//!
+//! The AEAD tag can't tell a wrong password from altered data. To report them
+//! as distinct errors, a password verifier can be stored next to the encrypted
+//! key: it is 16 more bytes of the PBKDF2 output, right after the encryption
+//! key, so that it is checked before decrypting without changing the EMIP-3
+//! format nor deriving the key twice.
+
+use core::error::Error;
+use core::fmt;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use cryptoxide::chacha20poly1305::ChaCha20Poly1305;
+use cryptoxide::constant_time::CtEqual;
+use cryptoxide::{hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+
+use super::key::{PrivateKeyError, XPrv, XPRV_SIZE};
+use super::securemem;
+
+/// Size of the salt of the password based encryption
+pub const PASSWORD_SALT_SIZE: usize = 32;
+
+/// Size of the nonce of the password based encryption
+pub const PASSWORD_NONCE_SIZE: usize = 12;
+
+/// Size of the password verifier
+pub const PASSWORD_VERIFIER_SIZE: usize = 16;
+
+const TAG_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+const ITERATIONS: u32 = 19_162;
+
+/// Size of a password encrypted `XPrv`
+pub const ENCRYPTED_XPRV_SIZE: usize =
+    PASSWORD_SALT_SIZE + PASSWORD_NONCE_SIZE + TAG_SIZE + XPRV_SIZE;
+
+const NONCE_OFFSET: usize = PASSWORD_SALT_SIZE;
+const TAG_OFFSET: usize = NONCE_OFFSET + PASSWORD_NONCE_SIZE;
+const DATA_OFFSET: usize = TAG_OFFSET + TAG_SIZE;
+
+/// Possible errors when decrypting a password encrypted `XPrv`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordEncryptionError {
+    /// the encrypted data is not of size `ENCRYPTED_XPRV_SIZE`
+    InvalidLength(usize),
+    /// the authentication tag does not match: either the password is wrong
+    /// or the encrypted data has been altered. Without a password verifier,
+    /// the AEAD construction cannot tell these two cases apart.
+    AuthenticationFailed,
+    /// the password does not match the password verifier
+    WrongPassword,
+    /// the password matches the password verifier, but the authentication
+    /// tag does not match: the encrypted data has been altered
+    Tampered,
+    /// the data has been decrypted but is not a valid `XPrv`
+    InvalidPrivateKey(PrivateKeyError),
+}
+
+// the PBKDF2 output: the encryption key followed by the password verifier.
+// The output is one SHA-512 block, whose first `KEY_SIZE` bytes are the
+// EMIP-3 key of a `KEY_SIZE` bytes long output.
+fn password_keys(password: &[u8], salt: &[u8]) -> [u8; KEY_SIZE + PASSWORD_VERIFIER_SIZE] {
+    let mut mac = Hmac::new(Sha512::new(), password);
+    let mut keys = [0u8; KEY_SIZE + PASSWORD_VERIFIER_SIZE];
+    pbkdf2(&mut mac, salt, ITERATIONS, &mut keys);
+    keys
+}
+
+/// encrypt any data with the EMIP-3 scheme, the result is the
+/// `salt | nonce | tag` header followed by the encrypted data, and the
+/// password verifier.
+pub(crate) fn encrypt(
+    password: &[u8],
+    salt: &[u8; PASSWORD_SALT_SIZE],
+    nonce: &[u8; PASSWORD_NONCE_SIZE],
+    data: &[u8],
+) -> (Vec<u8>, [u8; PASSWORD_VERIFIER_SIZE]) {
+    let mut keys = password_keys(password, salt);
+    let mut out = vec![0u8; DATA_OFFSET + data.len()];
+    out[..NONCE_OFFSET].copy_from_slice(salt);
+    out[NONCE_OFFSET..TAG_OFFSET].copy_from_slice(nonce);
+
+    let (header, encrypted) = out.split_at_mut(DATA_OFFSET);
+    let mut cipher = ChaCha20Poly1305::new(&keys[..KEY_SIZE], nonce, &[]);
+    cipher.encrypt(data, encrypted, &mut header[TAG_OFFSET..]);
+
+    let mut verifier = [0u8; PASSWORD_VERIFIER_SIZE];
+    verifier.copy_from_slice(&keys[KEY_SIZE..]);
+    securemem::zero(&mut keys);
+    (out, verifier)
+}
+
+/// decrypt data encrypted with `encrypt` into `out`, which must be the size
+/// of the encrypted data without its header. The password is checked first
+/// against the verifier if there is one. On error, the content of `out` is
+/// unspecified.
+pub(crate) fn decrypt(
+    password: &[u8],
+    encrypted: &[u8],
+    verifier: Option<&[u8; PASSWORD_VERIFIER_SIZE]>,
+    out: &mut [u8],
+) -> Result<(), PasswordEncryptionError> {
+    let salt = &encrypted[..NONCE_OFFSET];
+    let nonce = &encrypted[NONCE_OFFSET..TAG_OFFSET];
+    let tag = &encrypted[TAG_OFFSET..DATA_OFFSET];
+
+    let mut keys = password_keys(password, salt);
+    let result = match verifier {
+        Some(verifier) if !bool::from(keys[KEY_SIZE..].ct_eq(&verifier[..])) => {
+            Err(PasswordEncryptionError::WrongPassword)
+        }
+        _ => {
+            let mut cipher = ChaCha20Poly1305::new(&keys[..KEY_SIZE], nonce, &[]);
+            if cipher.decrypt(&encrypted[DATA_OFFSET..], out, tag) {
+                Ok(())
+            } else if verifier.is_some() {
+                Err(PasswordEncryptionError::Tampered)
+            } else {
+                Err(PasswordEncryptionError::AuthenticationFailed)
+            }
+        }
+    };
+    securemem::zero(&mut keys);
+    result
+}
+
+impl XPrv {
+    /// encrypt the `XPrv` with the given password
+    ///
+    /// The salt and the nonce must be generated by the caller with a secure
+    /// random number generator, and never be reused with the same password.
+    /// The result is `ENCRYPTED_XPRV_SIZE` bytes long.
+    pub fn encrypt_with_password(
+        &self,
+        password: &[u8],
+        salt: &[u8; PASSWORD_SALT_SIZE],
+        nonce: &[u8; PASSWORD_NONCE_SIZE],
+    ) -> Vec<u8> {
+        encrypt(password, salt, nonce, self.as_ref()).0
+    }
+
+    /// same as `XPrv::encrypt_with_password`, also returning the password
+    /// verifier to store along with the encrypted key
+    ///
+    /// The verifier is not part of the EMIP-3 format, other implementations
+    /// only need the encrypted key.
+    pub fn encrypt_with_password_verifier(
+        &self,
+        password: &[u8],
+        salt: &[u8; PASSWORD_SALT_SIZE],
+        nonce: &[u8; PASSWORD_NONCE_SIZE],
+    ) -> (Vec<u8>, [u8; PASSWORD_VERIFIER_SIZE]) {
+        encrypt(password, salt, nonce, self.as_ref())
+    }
+
+    /// decrypt a `XPrv` encrypted with `XPrv::encrypt_with_password`
+    ///
+    /// A wrong password and altered data both fail with
+    /// `PasswordEncryptionError::AuthenticationFailed`, see
+    /// `XPrv::decrypt_with_password_verifier` to tell them apart.
+    pub fn decrypt_with_password(
+        password: &[u8],
+        encrypted: &[u8],
+    ) -> Result<Self, PasswordEncryptionError> {
+        decrypt_xprv(password, encrypted, None)
+    }
+
+    /// decrypt a `XPrv` encrypted with `XPrv::encrypt_with_password_verifier`
+    ///
+    /// The password is checked against the verifier before decrypting:
+    /// a wrong password fails with `PasswordEncryptionError::WrongPassword`,
+    /// and altered encrypted data with `PasswordEncryptionError::Tampered`.
+    /// The salt is part of the password check, an altered salt is reported
+    /// as a wrong password.
+    pub fn decrypt_with_password_verifier(
+        password: &[u8],
+        encrypted: &[u8],
+        verifier: &[u8; PASSWORD_VERIFIER_SIZE],
+    ) -> Result<Self, PasswordEncryptionError> {
+        decrypt_xprv(password, encrypted, Some(verifier))
+    }
+}
+
+fn decrypt_xprv(
+    password: &[u8],
+    encrypted: &[u8],
+    verifier: Option<&[u8; PASSWORD_VERIFIER_SIZE]>,
+) -> Result<XPrv, PasswordEncryptionError> {
+    if encrypted.len() != ENCRYPTED_XPRV_SIZE {
+        return Err(PasswordEncryptionError::InvalidLength(encrypted.len()));
+    }
+    let mut bytes = [0u8; XPRV_SIZE];
+    let result = decrypt(password, encrypted, verifier, &mut bytes).and_then(|()| {
+        XPrv::from_slice_verified(&bytes).map_err(PasswordEncryptionError::InvalidPrivateKey)
+    });
+    securemem::zero(&mut bytes);
+    result
+}
+
+impl fmt::Display for PasswordEncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordEncryptionError::InvalidLength(length) => write!(
+                f,
+                "Invalid encrypted key length, expected {} bytes but got {}",
+                ENCRYPTED_XPRV_SIZE, length
+            ),
+            PasswordEncryptionError::AuthenticationFailed => {
+                f.write_str("Wrong password or altered encrypted key")
+            }
+            PasswordEncryptionError::WrongPassword => f.write_str("Wrong password"),
+            PasswordEncryptionError::Tampered => f.write_str("Altered encrypted key"),
+            PasswordEncryptionError::InvalidPrivateKey(e) => {
+                write!(f, "Invalid decrypted private key: {}", e)
+            }
+        }
+    }
+}
+impl Error for PasswordEncryptionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PasswordEncryptionError::InvalidPrivateKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
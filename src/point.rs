@@ -0,0 +1,44 @@
+//! Checks on the encoded points of the public keys
+
+use cryptoxide::curve25519::{Ge, GePartial, Scalar};
+
+/// encoding of the identity point (x = 0, y = 1)
+const IDENTITY: [u8; 32] = [
+    1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// order of the prime order subgroup, 2^252 + 27742317777372353535851937790883648493
+const L: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// decode a point, which must be in its canonical encoding: y is reduced
+/// modulo p, and the sign bit is not set when x is 0.
+///
+/// Like `Ge::from_bytes`, the negation of the encoded point is returned.
+/// It has the same order as the encoded point.
+pub(crate) fn decode_canonical(bytes: &[u8; 32]) -> Option<Ge> {
+    let neg_point = Ge::from_bytes(bytes)?;
+    let point = (&Ge::ZERO - &neg_point.to_cached()).to_full();
+    if &point.to_bytes() == bytes {
+        Some(neg_point)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn is_identity(point: &Ge) -> bool {
+    point.to_bytes() == IDENTITY
+}
+
+/// whether the order of the point divides 8, the cofactor of the curve
+pub(crate) fn is_small_order(point: &Ge) -> bool {
+    is_identity(&point.double().double().double())
+}
+
+/// whether the point is in the prime order subgroup, i.e. has no torsion component
+pub(crate) fn is_torsion_free(point: &Ge) -> bool {
+    let l = Scalar::from_bytes(&L);
+    GePartial::double_scalarmult_vartime(&l, point.clone(), &Scalar::ZERO).to_bytes() == IDENTITY
+}
@@ -0,0 +1,77 @@
+//! Arithmetic modulo the order of the base point
+//!
+//! cryptoxide only exposes the reduction of 512 bits integers, the products
+//! and sums are computed here on 512 bits integers and reduced at the end.
+//!
+//! The scalars may be secret (e.g. the private scalar and the nonce of a
+//! signature), so the intermediate limbs and bytes are wiped after use.
+
+use cryptoxide::curve25519::Scalar;
+
+use super::securemem;
+
+/// 512 bits little endian integer
+pub(crate) type Wide = [u64; 8];
+
+fn limbs(bytes: &[u8; 32]) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(8)) {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        *limb = u64::from_le_bytes(word);
+        securemem::zero(&mut word);
+    }
+    limbs
+}
+
+/// product of two 256 bits little endian integers
+pub(crate) fn mul_wide(a: &[u8; 32], b: &[u8; 32]) -> Wide {
+    let (mut a, mut b) = (limbs(a), limbs(b));
+    let mut out = [0u64; 8];
+    for i in 0..4 {
+        let mut carry = 0u128;
+        for j in 0..4 {
+            let t = (a[i] as u128) * (b[j] as u128) + (out[i + j] as u128) + carry;
+            out[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        out[i + 4] = carry as u64;
+    }
+    securemem::overwrite(&mut a, [0; 4]);
+    securemem::overwrite(&mut b, [0; 4]);
+    out
+}
+
+/// add to the accumulator, the caller makes sure the sum doesn't overflow
+pub(crate) fn add_wide(acc: &mut Wide, x: &Wide) {
+    let mut carry = 0u128;
+    for (a, x) in acc.iter_mut().zip(x.iter()) {
+        let t = (*a as u128) + (*x as u128) + carry;
+        *a = t as u64;
+        carry = t >> 64;
+    }
+}
+
+pub(crate) fn reduce(wide: &Wide) -> Scalar {
+    let mut bytes = [0u8; 64];
+    for (chunk, limb) in bytes.chunks_mut(8).zip(wide.iter()) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    let scalar = Scalar::reduce_from_wide_bytes(&bytes);
+    securemem::zero(&mut bytes);
+    scalar
+}
+
+/// `a * b + c` modulo the order of the base point
+pub(crate) fn muladd(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> Scalar {
+    let mut wide = mul_wide(a, b);
+    let mut c_limbs = limbs(c);
+    let mut c_wide = [0u64; 8];
+    c_wide[0..4].copy_from_slice(&c_limbs);
+    add_wide(&mut wide, &c_wide);
+    let scalar = reduce(&wide);
+    securemem::overwrite(&mut c_limbs, [0; 4]);
+    securemem::overwrite(&mut c_wide, [0; 8]);
+    securemem::overwrite(&mut wide, [0; 8]);
+    scalar
+}
@@ -1,9 +1,195 @@
+use core::ptr;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+/// zero the given slice
+///
+/// The bytes are written with volatile writes followed by a compiler fence,
+/// so that the compiler doesn't elide the writes to memory that is never
+/// read again.
 pub fn zero(to_zero: &mut [u8]) {
-    // the unsafety of this call is bounded to the existence of the pointer
-    // and the accuracy of the length of the array.
-    //
-    // since to_zero existence is bound to live at least as long as the call
-    // of this function and that we use the length (in bytes) of the given
-    // slice, this call is safe.
-    unsafe { ::std::ptr::write_bytes(to_zero.as_mut_ptr(), 0, to_zero.len()) }
+    for byte in to_zero.iter_mut() {
+        // the pointer comes from a mutable reference, so it is valid and
+        // aligned for the write.
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+/// overwrite the given value in place, e.g. a hashing context that has
+/// absorbed a secret and that can't be zeroed from the outside.
+///
+/// The write is volatile and followed by a compiler fence, so that it isn't
+/// elided even though the value is never read again. The previous value is
+/// not dropped, so this is only meant for plain data.
+pub fn overwrite<T>(to_overwrite: &mut T, value: T) {
+    // the pointer comes from a mutable reference, so it is valid and aligned
+    // for the write. Not running the destructor of the previous value is safe.
+    unsafe { ptr::write_volatile(to_overwrite, value) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+pub use self::locked::SecureXPrv;
+
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+mod locked {
+    use core::fmt;
+    use core::ptr::{self, NonNull};
+    use core::slice;
+    use std::io;
+
+    use super::super::derivation::{DerivationIndex, DerivationScheme};
+    use super::super::key::{XPrv, XPub, XPRV_SIZE};
+    use super::super::signature::Signature;
+    use super::zero;
+
+    /// `XPrv` stored in locked memory (Linux only)
+    ///
+    /// The key lives in a page of its own, locked in RAM with `mlock` so
+    /// that it is never written to swap, excluded from core dumps with
+    /// `MADV_DONTDUMP`, and surrounded by two inaccessible guard pages.
+    /// The page is wiped, unlocked and unmapped on drop.
+    ///
+    /// Only the copy owned by the `SecureXPrv` is protected: the `XPrv`
+    /// it is created from, and the values returned by its methods, live in
+    /// ordinary memory.
+    pub struct SecureXPrv {
+        // start of the mapping: guard page | key page | guard page
+        mapping: NonNull<u8>,
+        page_size: usize,
+    }
+
+    // the key page is owned by the `SecureXPrv`, and is only written when
+    // created and when dropped.
+    unsafe impl Send for SecureXPrv {}
+    unsafe impl Sync for SecureXPrv {}
+
+    fn check(result: libc::c_int) -> io::Result<()> {
+        if result == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    impl SecureXPrv {
+        /// move the given `XPrv` to a newly allocated locked page
+        ///
+        /// This fails if the page cannot be mapped or locked, for example
+        /// when the `RLIMIT_MEMLOCK` limit of the process is reached.
+        pub fn new(xprv: XPrv) -> io::Result<Self> {
+            let secure = Self::allocate()?;
+            secure.store(&xprv);
+            // `xprv` is wiped when dropped, on return
+            Ok(secure)
+        }
+
+        fn allocate() -> io::Result<Self> {
+            let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+            if page_size <= 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let page_size = page_size as usize;
+
+            let mapping = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    3 * page_size,
+                    libc::PROT_NONE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                    -1,
+                    0,
+                )
+            };
+            if mapping == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+
+            let page = unsafe { (mapping as *mut u8).add(page_size) as *mut libc::c_void };
+            let prepared = unsafe {
+                check(libc::mprotect(
+                    page,
+                    page_size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                ))
+                .and_then(|()| check(libc::madvise(page, page_size, libc::MADV_DONTDUMP)))
+                .and_then(|()| check(libc::mlock(page, page_size)))
+            };
+            if let Err(error) = prepared {
+                // nothing has been written to the page yet
+                unsafe { libc::munmap(mapping, 3 * page_size) };
+                return Err(error);
+            }
+
+            Ok(SecureXPrv {
+                mapping: NonNull::new(mapping as *mut u8).unwrap(),
+                page_size,
+            })
+        }
+
+        fn store(&self, xprv: &XPrv) {
+            // the page is writable and large enough for an `XPrv`, which
+            // only contains bytes and has no alignment requirement. The
+            // bytes are copied so that the source is dropped, and wiped,
+            // by its owner.
+            unsafe { ptr::copy_nonoverlapping(xprv.as_ref().as_ptr(), self.page(), XPRV_SIZE) };
+        }
+
+        fn page(&self) -> *mut u8 {
+            unsafe { self.mapping.as_ptr().add(self.page_size) }
+        }
+
+        fn xprv(&self) -> &XPrv {
+            // the page holds a valid `XPrv` from its creation until drop, and
+            // `XPrv` is a transparent wrapper of its bytes
+            unsafe { &*(self.page() as *const XPrv) }
+        }
+
+        /// get the `XPub` associated with this key, see `XPrv::public`
+        pub fn public(&self) -> XPub {
+            self.xprv().public()
+        }
+
+        /// sign the given message, see `XPrv::sign`
+        pub fn sign<T>(&self, message: &[u8]) -> Signature<T> {
+            self.xprv().sign(message)
+        }
+
+        /// verify a given signature, see `XPrv::verify`
+        pub fn verify<T>(&self, message: &[u8], signature: &Signature<T>) -> bool {
+            self.xprv().verify(message, signature)
+        }
+
+        /// derive the child key at the given index into a new locked page,
+        /// see `XPrv::derive`
+        pub fn derive<I: Into<DerivationIndex>>(
+            &self,
+            scheme: DerivationScheme,
+            index: I,
+        ) -> io::Result<Self> {
+            let child = Self::allocate()?;
+            child.store(&self.xprv().derive(scheme, index));
+            Ok(child)
+        }
+    }
+
+    impl Drop for SecureXPrv {
+        fn drop(&mut self) {
+            // the `XPrv` in the page is not dropped, the whole page is wiped instead
+            unsafe {
+                zero(slice::from_raw_parts_mut(self.page(), self.page_size));
+                libc::munlock(self.page() as *const libc::c_void, self.page_size);
+                libc::munmap(
+                    self.mapping.as_ptr() as *mut libc::c_void,
+                    3 * self.page_size,
+                );
+            }
+        }
+    }
+
+    impl fmt::Debug for SecureXPrv {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_tuple("SecureXPrv").field(self.xprv()).finish()
+        }
+    }
 }
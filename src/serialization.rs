@@ -0,0 +1,226 @@
+//! serde support
+//!
+//! Keys and signatures are serialized as hexadecimal strings in human readable
+//! formats (e.g. JSON) and as raw bytes otherwise. Derivation paths and indices
+//! use their textual representation in human readable formats and their raw
+//! `u32` values otherwise.
+//!
+//! `XPrv` is only serializable with the `serde-secret` feature.
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::FromStr;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+use super::derivation::{DerivationIndex, DerivationPath, DerivationScheme};
+use super::hex;
+#[cfg(feature = "serde-secret")]
+use super::key::XPrv;
+use super::key::XPub;
+use super::signature::Signature;
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&hex::encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+// Visitor of a byte encoded value, either as an hexadecimal string
+// or as bytes, and converted with `from_slice`
+struct BytesVisitor<T, F> {
+    expecting: &'static str,
+    from_slice: F,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, F, E> BytesVisitor<T, F>
+where
+    F: FnOnce(&[u8]) -> Result<T, E>,
+{
+    fn new(expecting: &'static str, from_slice: F) -> Self {
+        BytesVisitor {
+            expecting,
+            from_slice,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'de, T, F, E> Visitor<'de> for BytesVisitor<T, F>
+where
+    F: FnOnce(&[u8]) -> Result<T, E>,
+    E: fmt::Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<Er: de::Error>(self, v: &str) -> Result<T, Er> {
+        let mut bytes = hex::decode(v).map_err(Er::custom)?;
+        let value = (self.from_slice)(&bytes).map_err(Er::custom);
+        super::securemem::zero(&mut bytes);
+        value
+    }
+
+    fn visit_bytes<Er: de::Error>(self, v: &[u8]) -> Result<T, Er> {
+        (self.from_slice)(v).map_err(Er::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        let value = (self.from_slice)(&bytes).map_err(de::Error::custom);
+        super::securemem::zero(&mut bytes);
+        value
+    }
+}
+
+fn deserialize_bytes<'de, D, T, F, E>(
+    deserializer: D,
+    expecting: &'static str,
+    from_slice: F,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(&[u8]) -> Result<T, E>,
+    E: fmt::Display,
+{
+    let visitor = BytesVisitor::new(expecting, from_slice);
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(visitor)
+    } else {
+        deserializer.deserialize_bytes(visitor)
+    }
+}
+
+impl Serialize for XPub {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_ref(), serializer)
+    }
+}
+impl<'de> Deserialize<'de> for XPub {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_bytes(deserializer, "an extended public key", XPub::from_slice)
+    }
+}
+
+#[cfg(feature = "serde-secret")]
+impl Serialize for XPrv {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_ref(), serializer)
+    }
+}
+#[cfg(feature = "serde-secret")]
+impl<'de> Deserialize<'de> for XPrv {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_bytes(
+            deserializer,
+            "an extended private key",
+            XPrv::from_slice_verified,
+        )
+    }
+}
+
+impl<T> Serialize for Signature<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.as_ref(), serializer)
+    }
+}
+impl<'de, T> Deserialize<'de> for Signature<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_bytes(deserializer, "a signature", Signature::from_slice)
+    }
+}
+
+impl Serialize for DerivationScheme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            #[cfg(feature = "legacy-v1")]
+            DerivationScheme::V1 => serializer.serialize_str("V1"),
+            DerivationScheme::V2 => serializer.serialize_str("V2"),
+        }
+    }
+}
+#[cfg(feature = "legacy-v1")]
+const SCHEMES: &[&str] = &["V1", "V2"];
+#[cfg(not(feature = "legacy-v1"))]
+const SCHEMES: &[&str] = &["V2"];
+
+impl<'de> Deserialize<'de> for DerivationScheme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SchemeVisitor;
+        impl<'de> Visitor<'de> for SchemeVisitor {
+            type Value = DerivationScheme;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a derivation scheme")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match v {
+                    #[cfg(feature = "legacy-v1")]
+                    "V1" => Ok(DerivationScheme::V1),
+                    "V2" => Ok(DerivationScheme::V2),
+                    _ => Err(E::unknown_variant(v, SCHEMES)),
+                }
+            }
+        }
+        deserializer.deserialize_str(SchemeVisitor)
+    }
+}
+
+impl Serialize for DerivationIndex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u32(self.raw())
+        }
+    }
+}
+impl<'de> Deserialize<'de> for DerivationIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            DerivationIndex::from_str(&s).map_err(de::Error::custom)
+        } else {
+            u32::deserialize(deserializer).map(DerivationIndex::from)
+        }
+    }
+}
+
+impl Serialize for DerivationPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            let mut seq = serializer.serialize_seq(Some(self.len()))?;
+            for index in self.iter() {
+                seq.serialize_element(&index.raw())?;
+            }
+            seq.end()
+        }
+    }
+}
+impl<'de> Deserialize<'de> for DerivationPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            DerivationPath::from_str(&s).map_err(de::Error::custom)
+        } else {
+            let indices = Vec::<u32>::deserialize(deserializer)?;
+            Ok(indices.into_iter().map(DerivationIndex::from).collect())
+        }
+    }
+}
@@ -1,7 +1,8 @@
-use super::hex;
-use std::error::Error;
-use std::fmt;
-use std::marker::PhantomData;
+use super::hex::{self, HexError};
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::FromStr;
 
 use cryptoxide::constant_time::CtEqual;
 
@@ -9,9 +10,10 @@ use cryptoxide::constant_time::CtEqual;
 pub const SIGNATURE_SIZE: usize = 64;
 
 /// Possible errors during conversion from bytes
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SignatureError {
     InvalidLength(usize),
+    InvalidHex(HexError),
 }
 
 /// a signature with an associated type tag
@@ -24,7 +26,7 @@ pub struct Signature<T: ?Sized> {
 impl<T> Signature<T> {
     pub fn from_bytes(bytes: [u8; SIGNATURE_SIZE]) -> Self {
         Signature {
-            bytes: bytes,
+            bytes,
             _phantom: PhantomData,
         }
     }
@@ -42,7 +44,7 @@ impl<T> Signature<T> {
         Signature::<R>::from_bytes(self.bytes)
     }
 
-    pub fn to_bytes<'a>(&'a self) -> &'a [u8; SIGNATURE_SIZE] {
+    pub fn to_bytes(&self) -> &[u8; SIGNATURE_SIZE] {
         &self.bytes
     }
 }
@@ -62,6 +64,14 @@ impl<T> fmt::Debug for Signature<T> {
         write!(f, "{}", hex::encode(self.as_ref()))
     }
 }
+impl<T> FromStr for Signature<T> {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(SignatureError::InvalidHex)?;
+        Self::from_slice(&bytes)
+    }
+}
 impl<T> AsRef<[u8]> for Signature<T> {
     fn as_ref(&self) -> &[u8] {
         &self.bytes
@@ -75,6 +85,7 @@ impl fmt::Display for SignatureError {
                 "Invalid signature length, expected {} but got {}",
                 SIGNATURE_SIZE, length
             ),
+            SignatureError::InvalidHex(e) => write!(f, "Invalid hexadecimal signature: {}", e),
         }
     }
 }
@@ -18,7 +18,7 @@ const D1_H0: [u8; XPRV_SIZE] = [
     0x97, 0x60, 0xd1, 0xda, 0x74, 0xa6, 0xf5, 0xbd, 0x63, 0x3c, 0xe4, 0x1a, 0xdc, 0xee, 0xf0, 0x7a,
 ];
 
-const MSG: &'static [u8] = b"Hello World";
+const MSG: &[u8] = b"Hello World";
 
 const D1_H0_SIGNATURE: [u8; 64] = [
     0x90, 0x19, 0x4d, 0x57, 0xcd, 0xe4, 0xfd, 0xad, 0xd0, 0x1e, 0xb7, 0xcf, 0x16, 0x17, 0x80, 0xc2,
@@ -63,14 +63,14 @@ fn verify_signature() {
     assert!(prv.is_3rd_highest_bit_clear());
     let xpub = prv.public();
     let sig: Signature<u8> = Signature::from_slice(&D1_H0_SIGNATURE).unwrap();
-    assert_eq!(xpub.verify(MSG, &sig), true)
+    assert!(xpub.verify(MSG, &sig))
 }
 
 #[test]
 fn xprv_derive() {
     let prv = XPrv::from_bytes_verified(D1).unwrap();
     assert!(prv.is_3rd_highest_bit_clear());
-    derive_xprv_eq(&prv, 0x80000000, D1_H0);
+    derive_xprv_eq(&prv, DerivationIndex::hard(0).unwrap(), D1_H0);
 }
 
 #[test]
@@ -79,7 +79,1630 @@ fn marshall_xprv() {
     let xprv = XPrv::normalize_bytes_force3rd(bytes);
     let esk = xprv.extended_secret_key();
     let cc = xprv.chain_code();
-    let xprv2 = XPrv::from_extended_and_chaincode(&esk, &cc);
+    let xprv2 = XPrv::from_extended_and_chaincode(&esk, cc);
     assert_eq!(xprv.public(), xprv2.public());
     assert_eq!(cc, xprv.public().chain_code());
 }
+
+#[test]
+fn derivation_path_parse_and_display() {
+    let path: DerivationPath = "m/1852'/1815h/0H/0/5".parse().unwrap();
+    let expected: Vec<DerivationIndex> = vec![
+        DerivationIndex::hard(1852).unwrap(),
+        DerivationIndex::hard(1815).unwrap(),
+        DerivationIndex::hard(0).unwrap(),
+        DerivationIndex::soft(0).unwrap(),
+        DerivationIndex::soft(5).unwrap(),
+    ];
+    assert_eq!(path.as_slice(), &expected[..]);
+    assert_eq!(path.to_string(), "m/1852'/1815'/0'/0/5");
+    assert_eq!("1852'/1815'/0'/0/5".parse::<DerivationPath>(), Ok(path));
+    assert_eq!("m".parse::<DerivationPath>(), Ok(DerivationPath::new()));
+    assert_eq!(DerivationPath::new().to_string(), "m");
+}
+
+#[test]
+fn derivation_path_parse_errors() {
+    assert_eq!(
+        "m/".parse::<DerivationPath>(),
+        Err(DerivationPathParseError::EmptyComponent(0))
+    );
+    assert_eq!(
+        "m/0//1".parse::<DerivationPath>(),
+        Err(DerivationPathParseError::EmptyComponent(1))
+    );
+    assert_eq!(
+        "m/0/1x".parse::<DerivationPath>(),
+        Err(DerivationPathParseError::InvalidComponent(1))
+    );
+    assert_eq!(
+        "m/2147483648".parse::<DerivationPath>(),
+        Err(DerivationPathParseError::IndexOutOfRange(0))
+    );
+    assert_eq!(
+        "m/0/4294967296'".parse::<DerivationPath>(),
+        Err(DerivationPathParseError::IndexOutOfRange(1))
+    );
+}
+
+#[test]
+fn derivation_path_operations() {
+    let mut path: DerivationPath = "m/44'/0".parse().unwrap();
+    assert_eq!(path.child(3).to_string(), "m/44'/0/3");
+    assert_eq!(path.parent().unwrap().to_string(), "m/44'");
+    assert_eq!(path.pop(), Some(DerivationIndex::from(0)));
+    path.push(0x80000001);
+    assert_eq!(path.to_string(), "m/44'/1'");
+    assert_eq!(path.iter().count(), 2);
+    assert_eq!(DerivationPath::new().parent(), None);
+}
+
+#[test]
+fn derive_path() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let path: DerivationPath = "m/0'/1/2".parse().unwrap();
+    let expected = prv
+        .derive(DerivationScheme::V2, 0x80000000)
+        .derive(DerivationScheme::V2, 1)
+        .derive(DerivationScheme::V2, 2);
+    let child = prv.derive_path(DerivationScheme::V2, &path);
+    assert_eq!(child, expected);
+
+    let account = prv.derive(DerivationScheme::V2, 0x80000000).public();
+    let soft: DerivationPath = "m/1/2".parse().unwrap();
+    assert_eq!(
+        account.derive_path(DerivationScheme::V2, &soft).unwrap(),
+        expected.public()
+    );
+
+    let hard: DerivationPath = "m/1/2'/3".parse().unwrap();
+    assert_eq!(
+        account.derive_path(DerivationScheme::V2, &hard),
+        Err(DerivationPathError {
+            position: 1,
+            error: DerivationError::ExpectedSoftDerivation
+        })
+    );
+}
+
+#[test]
+fn derivation_index() {
+    let soft = DerivationIndex::soft(5).unwrap();
+    let hard = DerivationIndex::hard(5).unwrap();
+    assert!(!soft.is_hardened());
+    assert!(hard.is_hardened());
+    assert_eq!(soft.raw(), 5);
+    assert_eq!(hard.raw(), 0x80000005);
+    assert_eq!(hard.number(), 5);
+    assert_eq!(DerivationIndex::from(0x80000005), hard);
+    assert_eq!(u32::from(hard), 0x80000005);
+
+    assert_eq!(
+        DerivationIndex::soft(0x80000000),
+        Err(DerivationIndexError::OutOfRange)
+    );
+    assert_eq!(
+        DerivationIndex::hard(0x80000000),
+        Err(DerivationIndexError::OutOfRange)
+    );
+
+    assert_eq!(soft.next(), DerivationIndex::soft(6).ok());
+    assert_eq!(hard.next(), DerivationIndex::hard(6).ok());
+    assert_eq!(DerivationIndex::soft(0x7fffffff).unwrap().next(), None);
+    assert_eq!(DerivationIndex::hard(0x7fffffff).unwrap().next(), None);
+
+    assert_eq!(soft.to_string(), "5");
+    assert_eq!(hard.to_string(), "5'");
+    assert_eq!("5".parse(), Ok(soft));
+    assert_eq!("5'".parse(), Ok(hard));
+    assert_eq!("5h".parse(), Ok(hard));
+    assert_eq!("5H".parse(), Ok(hard));
+    assert_eq!(
+        "".parse::<DerivationIndex>(),
+        Err(DerivationIndexError::Empty)
+    );
+    assert_eq!(
+        "-5".parse::<DerivationIndex>(),
+        Err(DerivationIndexError::InvalidCharacter)
+    );
+    assert_eq!(
+        "2147483648'".parse::<DerivationIndex>(),
+        Err(DerivationIndexError::OutOfRange)
+    );
+}
+
+#[cfg(feature = "bip39")]
+const ICARUS_MNEMONIC: &str =
+    "eight country switch draw meat scout mystery blade tip drift useless good keep usage title";
+
+// Icarus master key generation test vectors from CIP-3
+#[cfg(feature = "bip39")]
+#[test]
+fn icarus_master_key() {
+    let vectors: [(&[u8], &str); 2] = [
+        (
+            b"",
+            "c065afd2832cd8b087c4d9ab7011f481ee1e0721e78ea5dd609f3ab3f156d245\
+             d176bd8fd4ec60b4731c3918a2a72a0226c0cd119ec35b47e4d55884667f552a\
+             23f7fdcd4a10c6cd2c7393ac61d877873e248f417634aa3d812af327ffe9d620",
+        ),
+        (
+            b"foo",
+            "70531039904019351e1afb361cd1b312a4d0565d4ff9f8062d38acf4b15cce41\
+             d7b5738d9c893feea55512a3004acb0d222c35d3e3d5cde943a15a9824cbac59\
+             443cf67e589614076ba01e354b1a432e0e6db3b59e37fc56b5fb0222970a010e",
+        ),
+    ];
+    for (passphrase, expected) in vectors.iter() {
+        let xprv = XPrv::from_mnemonic_icarus(ICARUS_MNEMONIC, passphrase).unwrap();
+        assert_eq!(hex::encode(xprv.as_ref()), *expected);
+        assert!(xprv.is_3rd_highest_bit_clear());
+    }
+}
+
+#[cfg(feature = "bip39")]
+#[test]
+fn bip39_mnemonic() {
+    let entropy = Entropy::from_mnemonic(ICARUS_MNEMONIC).unwrap();
+    assert_eq!(
+        hex::encode(entropy.as_ref()),
+        "46e62370a138a182a498b8e2885bc032379ddf38"
+    );
+    assert_eq!(entropy.to_mnemonic(), ICARUS_MNEMONIC);
+
+    let entropy = Entropy::from_slice(&[0xff; 32]).unwrap();
+    assert_eq!(
+        entropy.to_mnemonic(),
+        "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo \
+         zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote"
+    );
+    assert_eq!(
+        Entropy::from_mnemonic(&entropy.to_mnemonic())
+            .unwrap()
+            .as_ref(),
+        &[0xff; 32][..]
+    );
+
+    // 24 words vector of the BIP39 reference test vectors
+    let mnemonic = "letter advice cage absurd amount doctor acoustic avoid \
+                    letter advice cage absurd amount doctor acoustic avoid \
+                    letter advice cage absurd amount doctor acoustic bless";
+    let entropy = Entropy::from_mnemonic(mnemonic).unwrap();
+    assert_eq!(entropy.as_ref(), &[0x80; 32][..]);
+    assert_eq!(entropy.to_mnemonic(), mnemonic);
+    assert_eq!(
+        Entropy::from_mnemonic(&mnemonic.replace("bless", "zoo")).unwrap_err(),
+        Bip39Error::InvalidChecksum
+    );
+
+    assert_eq!(
+        Entropy::from_mnemonic("abandon abandon abandon").unwrap_err(),
+        Bip39Error::InvalidWordCount(3)
+    );
+    assert_eq!(
+        Entropy::from_mnemonic(&ICARUS_MNEMONIC.replace("draw", "drawn")).unwrap_err(),
+        Bip39Error::UnknownWord(3)
+    );
+    assert_eq!(
+        Entropy::from_mnemonic(&ICARUS_MNEMONIC.replace("title", "zoo")).unwrap_err(),
+        Bip39Error::InvalidChecksum
+    );
+    assert_eq!(
+        Entropy::from_slice(&[0; 17]).unwrap_err(),
+        Bip39Error::InvalidEntropySize(17)
+    );
+}
+
+// Ledger master key generation test vectors from CIP-3
+#[cfg(feature = "bip39")]
+#[test]
+fn ledger_master_key() {
+    let vectors: [(&str, &[u8], &str); 2] = [
+        (
+            "recall grace sport punch exhibit mad harbor stand obey short width stem \
+             awkward used stairs wool ugly trap season stove worth toward congress jaguar",
+            b"",
+            "a08cf85b564ecf3b947d8d4321fb96d70ee7bb760877e371899b14e2ccf88658\
+             104b884682b57efd97decbb318a45c05a527b9cc5c2f64f7352935a049ceea60\
+             680d52308194ccef2a18e6812b452a5815fbd7f5babc083856919aaf668fe7e4",
+        ),
+        (
+            "abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon art",
+            b"foo",
+            "f053a1e752de5c26197b60f032a4809f08bb3e5d90484fe42024be31efcba757\
+             8d914d3ff992e21652fee6a4d99f6091006938fac2c0c0f9d2de0ba64b754e92\
+             a4f3723f23472077aa4cd4dd8a8a175dba07ea1852dad1cf268c61a2679c3890",
+        ),
+    ];
+    for (phrase, passphrase, expected) in vectors.iter() {
+        let xprv = XPrv::from_mnemonic_ledger(phrase, passphrase).unwrap();
+        assert_eq!(hex::encode(xprv.as_ref()), *expected);
+        assert!(xprv.is_3rd_highest_bit_clear());
+    }
+}
+
+#[cfg(feature = "bip39")]
+#[test]
+fn trezor_master_key() {
+    // only 24 words mnemonic phrases differ from Icarus
+    let xprv = XPrv::from_mnemonic_trezor(ICARUS_MNEMONIC, b"").unwrap();
+    assert_eq!(
+        xprv,
+        XPrv::from_mnemonic_icarus(ICARUS_MNEMONIC, b"").unwrap()
+    );
+
+    // vector 9 of trezor-firmware crypto/tests/test_check_cardano.h, where
+    // the checksum byte of the 24 words mnemonic is part of the PBKDF2 salt
+    let phrase = "balance exotic ranch knife glory slow tape favorite yard gym awake \
+                  ill exist useless parent aim pig stay effort into square gasp credit \
+                  butter";
+    let xprv = XPrv::from_mnemonic_trezor(phrase, b"").unwrap();
+    assert_ne!(xprv, XPrv::from_mnemonic_icarus(phrase, b"").unwrap());
+    let path: DerivationPath = "m/0'/1'/2'/2'/1000000000'".parse().unwrap();
+    let xprv = xprv.derive_path(DerivationScheme::V2, &path);
+    assert_eq!(
+        hex::encode(xprv.as_ref()),
+        "38eb2a79486e516cb6658700503a3e2c870c03e9d1aec731f780aa6fb7f7de44\
+         80d2c677638e5dbd4395cdec279bf2a42077f2797c9e887949d37cdb317fce6a\
+         9b226add79f90086ea18b260da633089fe121db758aa31284ad1affaf3c9bb68"
+    );
+    assert_eq!(
+        hex::encode(xprv.public().public_key_slice()),
+        "115a365b2aad1d8eba7d379de518f1fa8553855110af24e5695011c32ce9a300"
+    );
+}
+
+#[test]
+fn bech32_roundtrip() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let encoded = prv.to_bech32(hrp::ROOT_XSK);
+    assert_eq!(
+        encoded,
+        "root_xsk1lz3fyv0w8rtvt0m3t4d6cgw82pth4gme3v3d08t9h7tad7k759dde50wr27l0z75hej8xxsjm6u56dn\
+         30pq396m0xe9cwxz3l5wf5frnsnde44sq8w7s3va3mhqdq7jew2fllp0fvxlj22enzf3wmhadp52m8gde"
+    );
+    assert_eq!(XPrv::from_bech32(&encoded, hrp::ROOT_XSK).unwrap(), prv);
+
+    let xpub = XPrv::from_bytes_verified(D1_H0).unwrap().public();
+    let encoded = xpub.to_bech32(hrp::ACCT_XVK);
+    assert_eq!(
+        encoded,
+        "acct_xvk1njvcgkhq5cygrhk00p6tjjks9e65qc5ttz8cksc96e5vc5ucd9uxppmrwu8dmamjfz4k22vykgdcf9mq\
+         68d8ffh4h43neeq6mnh0q7s8vhq7g"
+    );
+    assert_eq!(XPub::from_bech32(&encoded, hrp::ACCT_XVK).unwrap(), xpub);
+    assert_eq!(
+        XPub::from_bech32(&encoded.to_uppercase(), hrp::ACCT_XVK).unwrap(),
+        xpub
+    );
+
+    let signature: Signature<u8> = Signature::from_slice(&D1_H0_SIGNATURE).unwrap();
+    let encoded = signature.to_bech32(hrp::ED25519_SIG);
+    assert_eq!(
+        encoded,
+        "ed25519_sig1jqv5647dun76m5q7kl83v9uqcfm7z20uwy6mjame5vngsdlye5hfg39ehwgup6zdywa6suxl839a4\
+         ydpzrhhx43clfarf63qgm2tupq7frzj4"
+    );
+    assert_eq!(
+        Signature::<u8>::from_bech32(&encoded, hrp::ED25519_SIG).unwrap(),
+        signature
+    );
+}
+
+#[test]
+fn bech32_published_vectors() {
+    // valid checksums of BIP-173
+    let valid = [
+        "A12UEL5L",
+        "a12uel5l",
+        "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+        "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+        "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+        "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+        "?1ezyfcl",
+    ];
+    for s in valid.iter() {
+        let (hrp, data) = bech32::decode(s).unwrap();
+        assert_eq!(bech32::encode(&hrp, &data), s.to_lowercase());
+    }
+
+    // invalid strings of BIP-173, and bech32m (BIP-350) strings which
+    // don't have a valid bech32 checksum
+    let invalid = [
+        ("pzry9x0s0muk", Bech32Error::MissingSeparator),
+        ("1pzry9x0s0muk", Bech32Error::InvalidHrp),
+        ("x1b4n0q5v", Bech32Error::InvalidCharacter('b')),
+        ("li1dgmt3", Bech32Error::InvalidChecksum),
+        ("A1G7SGD8", Bech32Error::InvalidChecksum),
+        ("a1lqfn3a", Bech32Error::InvalidChecksum),
+        (
+            "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+            Bech32Error::InvalidChecksum,
+        ),
+    ];
+    for (s, error) in invalid.iter() {
+        assert_eq!(bech32::decode(s), Err(error.clone()), "{}", s);
+    }
+
+    // key of the cardano-serialization-lib tests, encoded with the
+    // xprv and xpub prefixes of the earlier Cardano tools
+    let encoded_xprv =
+        "xprv1hretan5mml3tq2p0twkhq4tz4jvka7m2l94kfr6yghkyfar6m9wppc7h9unw6p65y23kakzct3695rs32\
+         z7vaw3r2lg9scmfj8ec5du3ufydu5yuquxcz24jlkjhsc9vsa4ufzge9s00fn398svhacse5su2awrw";
+    let encoded_xpub =
+        "xpub1eamrnx3pph58yr5l4z2wghjpu2dt2f0rp0zq9qquqa39p52ct0xercjgmegfcpcdsy4t9ld90ps2epmtc\
+         jy3jtq77n8z20qe0m3pnfqntgrgj";
+    let prv = XPrv::from_bech32(encoded_xprv, Hrp::new("xprv")).unwrap();
+    assert_eq!(prv.to_bech32(Hrp::new("xprv")), encoded_xprv);
+    assert_eq!(prv.public().to_bech32(Hrp::new("xpub")), encoded_xpub);
+}
+
+#[test]
+fn bech32_errors() {
+    let xpub = XPrv::from_bytes_verified(D1_H0).unwrap().public();
+    let encoded = xpub.to_bech32(hrp::ACCT_XVK);
+
+    assert_eq!(
+        XPub::from_bech32(&encoded, hrp::ADDR_XVK),
+        Err(Bech32Error::UnexpectedHrp {
+            expected: "addr_xvk",
+            found: "acct_xvk".to_string()
+        })
+    );
+
+    let mut corrupted = encoded.clone().into_bytes();
+    let last = corrupted.len() - 1;
+    corrupted[last] = if corrupted[last] == b'q' { b'p' } else { b'q' };
+    assert_eq!(
+        XPub::from_bech32(std::str::from_utf8(&corrupted).unwrap(), hrp::ACCT_XVK),
+        Err(Bech32Error::InvalidChecksum)
+    );
+
+    let mixed = encoded.replacen("acct", "ACCT", 1);
+    assert_eq!(
+        XPub::from_bech32(&mixed, hrp::ACCT_XVK),
+        Err(Bech32Error::MixedCase)
+    );
+    assert_eq!(
+        XPub::from_bech32("acct_xvkqqqqqq", hrp::ACCT_XVK),
+        Err(Bech32Error::MissingSeparator)
+    );
+    assert_eq!(
+        XPub::from_bech32("acct_xvk1qqqqqqb", hrp::ACCT_XVK),
+        Err(Bech32Error::InvalidCharacter('b'))
+    );
+
+    // a valid bech32 string of the wrong size for the type
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let signature = prv.to_bech32(Hrp::new("ed25519_sig"));
+    assert_eq!(
+        Signature::<u8>::from_bech32(&signature, hrp::ED25519_SIG),
+        Err(Bech32Error::InvalidDataLength(XPRV_SIZE))
+    );
+
+    // a 96 bytes payload which is not a valid extended secret key
+    let mut bytes = D1;
+    bytes[0] |= 0b0000_0111;
+    let encoded = bech32::encode("root_xsk", &bytes);
+    assert_eq!(
+        XPrv::from_bech32(&encoded, hrp::ROOT_XSK),
+        Err(Bech32Error::InvalidPrivateKey(
+            PrivateKeyError::LowestBitsInvalid
+        ))
+    );
+
+    // prefixes are validated when created, and are case insensitive
+    assert_eq!(Hrp::<XPrv>::try_new(""), Err(Bech32Error::InvalidHrp));
+    assert_eq!(
+        Hrp::<XPrv>::try_new("root xsk"),
+        Err(Bech32Error::InvalidHrp)
+    );
+    let upper = Hrp::<XPrv>::try_new("ROOT_XSK").unwrap();
+    assert_eq!(upper, hrp::ROOT_XSK);
+    let encoded = prv.to_bech32(upper);
+    assert_eq!(encoded, prv.to_bech32(hrp::ROOT_XSK));
+    assert_eq!(XPrv::from_bech32(&encoded, upper).unwrap(), prv);
+    assert_eq!(
+        XPrv::from_bech32(&encoded.to_uppercase(), hrp::ROOT_XSK).unwrap(),
+        prv
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid bech32 human readable part")]
+fn bech32_empty_hrp() {
+    Hrp::<XPub>::new("");
+}
+
+#[test]
+fn hex_parse() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    assert_eq!(prv.to_hex_secret().parse::<XPrv>().unwrap(), prv);
+    assert_eq!(
+        prv.to_hex_secret().to_uppercase().parse::<XPrv>().unwrap(),
+        prv
+    );
+
+    let xpub = prv.public();
+    assert_eq!(xpub.to_string().parse::<XPub>().unwrap(), xpub);
+
+    let signature: Signature<u8> = Signature::from_slice(&D1_H0_SIGNATURE).unwrap();
+    assert_eq!(
+        signature.to_string().parse::<Signature<u8>>().unwrap(),
+        signature
+    );
+
+    assert_eq!(
+        "abc".parse::<XPub>().unwrap_err(),
+        PublicKeyError::HexInvalid(HexError::OddLength)
+    );
+    assert_eq!(
+        "0g".parse::<Signature<u8>>().unwrap_err(),
+        SignatureError::InvalidHex(HexError::InvalidCharacter(1))
+    );
+    assert_eq!(
+        "00".parse::<XPub>().unwrap_err(),
+        PublicKeyError::LengthInvalid(1)
+    );
+    assert_eq!(
+        "0000".parse::<Signature<u8>>().unwrap_err(),
+        SignatureError::InvalidLength(2)
+    );
+
+    let mut bytes = D1;
+    bytes[31] |= 0b1000_0000;
+    assert_eq!(
+        hex::encode(&bytes).parse::<XPrv>().unwrap_err(),
+        PrivateKeyError::HighestBitsInvalid
+    );
+    assert_eq!(
+        "x".repeat(XPRV_SIZE * 2).parse::<XPrv>().unwrap_err(),
+        PrivateKeyError::HexInvalid(HexError::InvalidCharacter(0))
+    );
+    let mut secret = hex::encode(&D1);
+    secret.replace_range(XPRV_SIZE * 2 - 1.., "x");
+    assert_eq!(
+        secret.parse::<XPrv>().unwrap_err(),
+        PrivateKeyError::HexInvalid(HexError::InvalidCharacter(XPRV_SIZE * 2 - 1))
+    );
+}
+
+#[cfg(feature = "serde")]
+const D1_H0_XPUB_HEX: &str = "9c99845ae0a60881decf7874b94ad02e7540628b588f8b4305d668cc53986978\
+                              608763770eddf77248ab652984b21b849760d1da74a6f5bd633ce41adceef07a";
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_xpub_and_signature() {
+    use serde_test::{assert_de_tokens_error, assert_tokens, Configure, Readable, Token};
+
+    let xpub: XPub = D1_H0_XPUB_HEX.parse().unwrap();
+    assert_eq!(xpub, XPrv::from_bytes_verified(D1_H0).unwrap().public());
+    let xpub_bytes: &'static [u8] = Box::leak(xpub.as_ref().to_vec().into_boxed_slice());
+    assert_tokens(&xpub.readable(), &[Token::Str(D1_H0_XPUB_HEX)]);
+    assert_tokens(&xpub.compact(), &[Token::Bytes(xpub_bytes)]);
+
+    let signature: Signature<u8> = Signature::from_slice(&D1_H0_SIGNATURE).unwrap();
+    let signature_hex: &'static str = Box::leak(signature.to_string().into_boxed_str());
+    assert_tokens(&signature.clone().readable(), &[Token::Str(signature_hex)]);
+    assert_tokens(&signature.compact(), &[Token::Bytes(&D1_H0_SIGNATURE)]);
+
+    assert_tokens(&DerivationScheme::V2, &[Token::Str("V2")]);
+
+    assert_de_tokens_error::<Readable<XPub>>(
+        &[Token::Str("0011")],
+        "Invalid public key length, expected 64 but received 2",
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_derivation_path() {
+    use serde_test::{assert_tokens, Configure, Token};
+
+    let path: DerivationPath = "m/1852'/1815'/0'/0/5".parse().unwrap();
+    assert_tokens(
+        &path.clone().readable(),
+        &[Token::Str("m/1852'/1815'/0'/0/5")],
+    );
+    assert_tokens(
+        &path.compact(),
+        &[
+            Token::Seq { len: Some(5) },
+            Token::U32(0x8000073c),
+            Token::U32(0x80000717),
+            Token::U32(0x80000000),
+            Token::U32(0),
+            Token::U32(5),
+            Token::SeqEnd,
+        ],
+    );
+
+    let index = DerivationIndex::hard(44).unwrap();
+    assert_tokens(&index.readable(), &[Token::Str("44'")]);
+    assert_tokens(&index.compact(), &[Token::U32(0x8000002c)]);
+}
+
+#[cfg(feature = "serde-secret")]
+#[test]
+fn serde_xprv() {
+    use serde_test::{assert_de_tokens_error, assert_tokens, Compact, Configure, Token};
+
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let prv_hex: &'static str = Box::leak(prv.to_hex_secret().into_boxed_str());
+    assert_tokens(&prv.clone().readable(), &[Token::Str(prv_hex)]);
+    assert_tokens(&prv.compact(), &[Token::Bytes(&D1)]);
+
+    let mut bytes = D1;
+    bytes[0] |= 0b0000_0111;
+    let bytes: &'static [u8] = Box::leak(Box::new(bytes));
+    assert_de_tokens_error::<Compact<XPrv>>(&[Token::Bytes(bytes)], "Invalid lowest bits");
+}
+
+#[test]
+fn xprv_redacted() {
+    let prv = XPrv::from_bytes_verified(D1_H0).unwrap();
+    let secret = prv.to_hex_secret();
+    let public_key = hex::encode(prv.public().public_key_slice());
+    assert_eq!(secret, hex::encode(&D1_H0));
+
+    assert!(format!("{:?}", prv).contains(&hex::encode(&prv.fingerprint())));
+    for shown in [format!("{:?}", prv), format!("{:#?}", prv), prv.to_string()].iter() {
+        assert!(shown.contains(&public_key));
+        assert!(!shown.contains(&secret[..64]));
+        assert!(!shown.contains(&secret[128..]));
+    }
+}
+
+const PASSWORD_SALT: [u8; PASSWORD_SALT_SIZE] = [
+    0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0x50, 0x51, 0x52, 0x53,
+    0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3,
+];
+const PASSWORD_NONCE: [u8; PASSWORD_NONCE_SIZE] = [
+    0x50, 0x51, 0x52, 0x53, 0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7,
+];
+
+// password, salt, nonce and data of the EMIP-3 test vector. The expected
+// output is the one of the EMIP-3 implementation of cardano-multiplatform-lib,
+// and of PBKDF2 and ChaCha20-Poly1305 from Python's hashlib and cryptography.
+#[test]
+fn password_encryption_emip3() {
+    let (encrypted, verifier) = password_encryption::encrypt(
+        b"password",
+        &PASSWORD_SALT,
+        &PASSWORD_NONCE,
+        b"some data to encrypt",
+    );
+    assert_eq!(
+        hex::encode(&encrypted),
+        "50515253c0c1c2c3c4c5c6c750515253c0c1c2c3c4c5c6c750515253c0c1c2c3\
+         50515253c0c1c2c3c4c5c6c7\
+         c266630887d216bf88cc4990f73bad7f\
+         35bc7c0225b38fe24a7c28b5f9bda6283e3c5768"
+    );
+    // the bytes of the PBKDF2 output following the encryption key, as
+    // computed by Python's hashlib
+    assert_eq!(hex::encode(&verifier), "626d2284a527c9c683c522bb0cbde01b");
+    let mut decrypted = [0u8; 20];
+    assert_eq!(
+        password_encryption::decrypt(b"password", &encrypted, None, &mut decrypted),
+        Ok(())
+    );
+    assert_eq!(&decrypted, b"some data to encrypt");
+}
+
+#[test]
+fn password_encryption() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let encrypted = prv.encrypt_with_password(b"password", &PASSWORD_SALT, &PASSWORD_NONCE);
+    assert_eq!(encrypted.len(), ENCRYPTED_XPRV_SIZE);
+    assert_eq!(
+        hex::encode(&encrypted),
+        "50515253c0c1c2c3c4c5c6c750515253c0c1c2c3c4c5c6c750515253c0c1c2c3\
+         50515253c0c1c2c3c4c5c6c7\
+         91830d40a0e214c231452046dc58984d\
+         be718356ebef3853942d018175f90f1b1b3f84657e50b395fec952f6df89a4f0\
+         42c836b32a354152c05aa9c50d95326c923c4056dd55fe1d8fc5217a56415052\
+         5310f87176c7e6abd4e8c24fb08adcbc4576ccf22c381264c86f9c9e50532014"
+    );
+
+    let decrypted = XPrv::decrypt_with_password(b"password", &encrypted).unwrap();
+    assert_eq!(decrypted, prv);
+
+    assert_eq!(
+        XPrv::decrypt_with_password(b"passw0rd", &encrypted),
+        Err(PasswordEncryptionError::AuthenticationFailed)
+    );
+    for &offset in [0, 40, 50, 100].iter() {
+        let mut altered = encrypted.clone();
+        altered[offset] ^= 0x01;
+        assert_eq!(
+            XPrv::decrypt_with_password(b"password", &altered),
+            Err(PasswordEncryptionError::AuthenticationFailed)
+        );
+    }
+    assert_eq!(
+        XPrv::decrypt_with_password(b"password", &encrypted[..ENCRYPTED_XPRV_SIZE - 1]),
+        Err(PasswordEncryptionError::InvalidLength(
+            ENCRYPTED_XPRV_SIZE - 1
+        ))
+    );
+    // with a password verifier, a wrong password and altered data are told apart
+    let (encrypted_verified, verifier) =
+        prv.encrypt_with_password_verifier(b"password", &PASSWORD_SALT, &PASSWORD_NONCE);
+    assert_eq!(encrypted_verified, encrypted);
+    assert_eq!(
+        XPrv::decrypt_with_password_verifier(b"password", &encrypted, &verifier).unwrap(),
+        prv
+    );
+    assert_eq!(
+        XPrv::decrypt_with_password_verifier(b"passw0rd", &encrypted, &verifier),
+        Err(PasswordEncryptionError::WrongPassword)
+    );
+    for &offset in [40, 50, 100].iter() {
+        let mut altered = encrypted.clone();
+        altered[offset] ^= 0x01;
+        assert_eq!(
+            XPrv::decrypt_with_password_verifier(b"password", &altered, &verifier),
+            Err(PasswordEncryptionError::Tampered)
+        );
+    }
+    let mut altered_verifier = verifier;
+    altered_verifier[0] ^= 0x01;
+    assert_eq!(
+        XPrv::decrypt_with_password_verifier(b"password", &encrypted, &altered_verifier),
+        Err(PasswordEncryptionError::WrongPassword)
+    );
+}
+
+// V1 vectors produced by ed25519-bip32 0.3.2, the last release with V1
+#[cfg(feature = "legacy-v1")]
+#[test]
+fn legacy_v1_derivation() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let h0 = prv.derive(DerivationScheme::V1, 0x80000000);
+    assert_eq!(
+        hex::encode(h0.as_ref()),
+        "841426d70723375df1911d20a8e4238026a3134a14fbaf2e16d8a7fe229fda02\
+         a2fe999a4d368893dffa3816a2d4515362951e0b70fb77307d9833979d54ebe1\
+         0395a8fda3c7e3fcd9b17c9d747b9574fa0311ff2e394ffcd3174586f44f232d"
+    );
+
+    let h0_0 = h0.derive(DerivationScheme::V1, 0);
+    assert_eq!(
+        hex::encode(h0_0.as_ref()),
+        "da94923dfb9c925ddd30afa29319a62e7f131432f59310872e30408f4befd203\
+         6efc343b61ab369638340e10c4f586a11e233380096cc8e4577b3bc798ae12de\
+         a1389323bdb58b8433158f7b2c6fbc8cc536d286045e3764354d998422222658"
+    );
+    let xpub = h0.public().derive(DerivationScheme::V1, 0).unwrap();
+    assert_eq!(
+        hex::encode(xpub.as_ref()),
+        "93d3f9093bb2906f1c87fc692fc4cf8404b7a149f9be23fe28f66158f3f1742a\
+         a1389323bdb58b8433158f7b2c6fbc8cc536d286045e3764354d998422222658"
+    );
+    assert_eq!(xpub, h0_0.public());
+
+    let path: DerivationPath = "m/0'/1'/2".parse().unwrap();
+    assert_eq!(
+        hex::encode(prv.derive_path(DerivationScheme::V1, &path).as_ref()),
+        "b287be8e0d345de4a8ab1c2481c33669d60b2debd4cb71afa7c818772330040c\
+         326e18226cb1e548f4a969ea28c79d33e57d07914fadfdf9035b42e94206dcb4\
+         603b64a3f23af5db4e996bc9acf8dec950e40063476a583e2c8624e209034aee"
+    );
+}
+
+#[test]
+fn derive_checked() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let child = prv
+        .derive_checked(DerivationScheme::V2, 0, 0x80000000)
+        .unwrap();
+    assert_eq!(child, prv.derive(DerivationScheme::V2, 0x80000000));
+    let xpub = child.public();
+    assert_eq!(
+        xpub.derive_checked(DerivationScheme::V2, 1, 0).unwrap(),
+        xpub.derive(DerivationScheme::V2, 0).unwrap()
+    );
+
+    let last = MAX_DERIVATION_DEPTH - 1;
+    assert!(prv.derive_checked(DerivationScheme::V2, last, 0).is_ok());
+    assert_eq!(
+        prv.derive_checked(DerivationScheme::V2, MAX_DERIVATION_DEPTH, 0),
+        Err(DerivationError::DepthExceeded(MAX_DERIVATION_DEPTH + 1))
+    );
+    assert_eq!(
+        xpub.derive_checked(DerivationScheme::V2, u32::MAX, 0),
+        Err(DerivationError::DepthExceeded(u32::MAX))
+    );
+
+    let path: DerivationPath = "m/1852'/1815'/0'/0/0".parse().unwrap();
+    assert_eq!(
+        prv.derive_path_checked(DerivationScheme::V2, 0, &path)
+            .unwrap(),
+        prv.derive_path(DerivationScheme::V2, &path)
+    );
+    assert_eq!(
+        prv.derive_path_checked(DerivationScheme::V2, last - 2, &path),
+        Err(DerivationPathError {
+            position: 3,
+            error: DerivationError::DepthExceeded(MAX_DERIVATION_DEPTH + 1)
+        })
+    );
+    let path: DerivationPath = "m/0/0".parse().unwrap();
+    assert_eq!(
+        xpub.derive_path_checked(DerivationScheme::V2, last, &path),
+        Err(DerivationPathError {
+            position: 1,
+            error: DerivationError::DepthExceeded(MAX_DERIVATION_DEPTH + 1)
+        })
+    );
+
+    // a scalar just below 2^255 overflows the valid range when derived
+    let mut bytes = D1;
+    bytes[0..32].copy_from_slice(&[0xff; 32]);
+    bytes[0] = 0xf8;
+    bytes[31] = 0x7f;
+    let prv = XPrv::from_bytes_verified(bytes).unwrap();
+    assert_eq!(
+        prv.derive_checked(DerivationScheme::V2, 0, 0x80000000),
+        Err(DerivationError::InvalidScalar)
+    );
+}
+
+#[test]
+fn extended_keys() {
+    let master = ExtendedXPrv::master(XPrv::from_bytes_verified(D1).unwrap());
+    assert_eq!(master.depth(), 0);
+    assert_eq!(master.parent_fingerprint(), [0; FINGERPRINT_SIZE]);
+    assert_eq!(master.child_index(), DerivationIndex::from_raw(0));
+
+    let account = master.derive(DerivationScheme::V2, 0x80000000).unwrap();
+    assert_eq!(account.xprv(), &XPrv::from_bytes_verified(D1_H0).unwrap());
+    assert_eq!(account.depth(), 1);
+    assert_eq!(account.parent_fingerprint(), [0x24, 0xff, 0x65, 0x0e]);
+    assert_eq!(account.child_index(), DerivationIndex::hard(0).unwrap());
+
+    let bytes = account.to_bytes();
+    assert_eq!(bytes.len(), EXTENDED_XPRV_SIZE);
+    assert_eq!(hex::encode(&bytes[..12]), "0000000124ff650e80000000");
+    assert_eq!(&bytes[12..], &D1_H0[..]);
+    assert_eq!(ExtendedXPrv::from_slice(&bytes).unwrap(), account);
+
+    let path: DerivationPath = "m/1/2".parse().unwrap();
+    let address = account.derive_path(DerivationScheme::V2, &path).unwrap();
+    let address_pub = account
+        .public()
+        .derive_path(DerivationScheme::V2, &path)
+        .unwrap();
+    assert_eq!(address.public(), address_pub);
+    assert_eq!(address_pub.depth(), 3);
+    assert_eq!(address_pub.child_index(), DerivationIndex::soft(2).unwrap());
+    let bytes = address_pub.to_bytes();
+    assert_eq!(bytes.len(), EXTENDED_XPUB_SIZE);
+    assert_eq!(ExtendedXPub::from_slice(&bytes).unwrap(), address_pub);
+
+    assert_eq!(
+        account.public().derive(DerivationScheme::V2, 0x80000000),
+        Err(DerivationError::ExpectedSoftDerivation)
+    );
+    assert_eq!(
+        ExtendedXPub::from_slice(&bytes[1..]),
+        Err(ExtendedKeyError::InvalidLength(EXTENDED_XPUB_SIZE - 1))
+    );
+    let mut bytes = account.to_bytes();
+    bytes[12] |= 0x01;
+    assert_eq!(
+        ExtendedXPrv::from_slice(&bytes),
+        Err(ExtendedKeyError::InvalidPrivateKey(
+            PrivateKeyError::LowestBitsInvalid
+        ))
+    );
+}
+
+#[test]
+fn key_identifier() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let xpub = prv.public();
+    assert_eq!(
+        hex::encode(&xpub.identifier()),
+        "24ff650ed7d917c7798d689a5687c80afe48d7ce1fdf4354a404803b"
+    );
+    assert_eq!(xpub.fingerprint(), [0x24, 0xff, 0x65, 0x0e]);
+    assert_eq!(prv.identifier(), xpub.identifier());
+    assert_eq!(prv.fingerprint(), xpub.fingerprint());
+
+    let child = prv.derive(DerivationScheme::V2, 0x80000000);
+    assert_ne!(child.fingerprint(), prv.fingerprint());
+}
+
+#[test]
+fn derive_range() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let children = prv.derive_range(DerivationScheme::V2, 0x7ffffffe..0x80000002);
+    let expected: Vec<XPrv> = (0x7ffffffe..0x80000002)
+        .map(|index| prv.derive(DerivationScheme::V2, index))
+        .collect();
+    assert_eq!(children, expected);
+    assert!(prv.derive_range(DerivationScheme::V2, 5..5).is_empty());
+
+    let xpub = prv.public();
+    let children = xpub.derive_range(DerivationScheme::V2, 0..20).unwrap();
+    assert_eq!(children.len(), 20);
+    for (index, child) in (0..20).zip(children.iter()) {
+        assert_eq!(child, &xpub.derive(DerivationScheme::V2, index).unwrap());
+        assert_eq!(child, &prv.derive(DerivationScheme::V2, index).public());
+    }
+    assert_eq!(
+        xpub.derive_range(DerivationScheme::V2, 0x7fffffff..0x80000001),
+        Err(DerivationError::ExpectedSoftDerivation)
+    );
+}
+
+#[test]
+fn prepared_xpub() {
+    let xpub = XPrv::from_bytes_verified(D1_H0).unwrap().public();
+    let prepared = PreparedXPub::new(&xpub, DerivationScheme::V2).unwrap();
+    assert_eq!(prepared.xpub(), &xpub);
+    for index in [0, 1, 19, 0x7fffffff].iter() {
+        assert_eq!(
+            prepared.derive(*index).unwrap(),
+            xpub.derive(DerivationScheme::V2, *index).unwrap()
+        );
+    }
+    assert_eq!(
+        prepared.derive_range(0..10).unwrap(),
+        xpub.derive_range(DerivationScheme::V2, 0..10).unwrap()
+    );
+    assert_eq!(
+        prepared.derive(0x80000000),
+        Err(DerivationError::ExpectedSoftDerivation)
+    );
+
+    // y = 2 is not the coordinate of a point of the curve
+    let mut bytes = [0u8; XPUB_SIZE];
+    bytes[0] = 2;
+    assert_eq!(
+        PreparedXPub::new(&XPub::from_bytes(bytes), DerivationScheme::V2).unwrap_err(),
+        DerivationError::InvalidAddition
+    );
+}
+
+#[test]
+fn xpub_verified() {
+    use cryptoxide::curve25519::Ge;
+
+    fn xpub_with_pk(pk: &[u8; 32]) -> [u8; XPUB_SIZE] {
+        let mut bytes = [0u8; XPUB_SIZE];
+        bytes[0..32].copy_from_slice(pk);
+        bytes
+    }
+    fn pk_from_hex(s: &str) -> [u8; 32] {
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(&hex::decode(s).unwrap());
+        pk
+    }
+
+    let xpub = XPrv::from_bytes_verified(D1).unwrap().public();
+    assert_eq!(XPub::from_slice_verified(xpub.as_ref()), Ok(xpub));
+    assert_eq!(
+        XPub::from_slice_verified_prime_order(xpub.as_ref()),
+        Ok(xpub)
+    );
+    assert_eq!(
+        XPub::from_slice_verified(&xpub.as_ref()[1..]),
+        Err(PublicKeyError::LengthInvalid(XPUB_SIZE - 1))
+    );
+
+    let invalid = [
+        // y = 2 is not the coordinate of a point of the curve
+        "0200000000000000000000000000000000000000000000000000000000000000",
+        // y = p + 1, non canonical encoding of the identity
+        "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        // x = 0 with the sign bit set, non canonical encoding of the identity
+        "0100000000000000000000000000000000000000000000000000000000000080",
+    ];
+    for pk in invalid.iter() {
+        assert_eq!(
+            XPub::from_bytes_verified(xpub_with_pk(&pk_from_hex(pk))),
+            Err(PublicKeyError::PointInvalid)
+        );
+    }
+
+    let small_order = [
+        // identity
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        // order 2
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        // order 4
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        // order 8
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a",
+    ];
+    for pk in small_order.iter() {
+        assert_eq!(
+            XPub::from_bytes_verified(xpub_with_pk(&pk_from_hex(pk))),
+            Err(PublicKeyError::PointSmallOrder)
+        );
+    }
+
+    // the public key plus a point of order 8
+    let neg_pk = Ge::from_bytes(xpub.public_key_bytes()).unwrap();
+    let neg_torsion = Ge::from_bytes(&pk_from_hex(small_order[4])).unwrap();
+    let neg_mixed = (&neg_pk + &neg_torsion.to_cached()).to_full();
+    let mixed = (&Ge::ZERO - &neg_mixed.to_cached()).to_full().to_bytes();
+    assert!(XPub::from_bytes_verified(xpub_with_pk(&mixed)).is_ok());
+    assert_eq!(
+        XPub::from_bytes_verified_prime_order(xpub_with_pk(&mixed)),
+        Err(PublicKeyError::PointMixedTorsion)
+    );
+}
+
+struct EdgeCase {
+    name: &'static str,
+    public_key: &'static str,
+    message: &'static [u8],
+    signature: &'static str,
+    strict: bool,
+    cofactored: bool,
+    zip215: bool,
+}
+
+// more edge cases in the categories of "Taming the many EdDSAs", the expected
+// results of the strict and ZIP-215 modes match ed25519-dalek's `verify_strict`
+// and ed25519-zebra
+const EDGE_CASES: [EdgeCase; 10] = [
+    EdgeCase {
+        name: "valid signature",
+        public_key: "58c1a2f118f1acf0772e0d26276e19afe98f006a383b3c7ccca07ec0ad56ba6b",
+        message: b"edge case message",
+        signature: "490785e99bac756ea777e75305249abf13fba8989b35a01e515078ac0cea85cf\
+                    4d44f2b9403773c8ab70b419c767a3cb3d4011213d7ffa52e2287897f55bbc08",
+        strict: true,
+        cofactored: true,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "signature of another message",
+        public_key: "58c1a2f118f1acf0772e0d26276e19afe98f006a383b3c7ccca07ec0ad56ba6b",
+        message: b"edge case message!",
+        signature: "490785e99bac756ea777e75305249abf13fba8989b35a01e515078ac0cea85cf\
+                    4d44f2b9403773c8ab70b419c767a3cb3d4011213d7ffa52e2287897f55bbc08",
+        strict: false,
+        cofactored: false,
+        zip215: false,
+    },
+    EdgeCase {
+        name: "S not reduced modulo L",
+        public_key: "58c1a2f118f1acf0772e0d26276e19afe98f006a383b3c7ccca07ec0ad56ba6b",
+        message: b"edge case message",
+        signature: "490785e99bac756ea777e75305249abf13fba8989b35a01e515078ac0cea85cf\
+                    3a18e8165b9a8520820dacbca56182e03d4011213d7ffa52e2287897f55bbc18",
+        strict: false,
+        cofactored: false,
+        zip215: false,
+    },
+    EdgeCase {
+        name: "A with a torsion component",
+        public_key: "7764ddf619dee02e9c195ebee1961c6dc2989ef6b2193c8c9f84bc1846cf6590",
+        message: b"mixed A 0",
+        signature: "970032809374a5751053b56a3760fa9ca7d74963564486f60128cdecd008a718\
+                    103ebf99114ffa2c7b93e9b5d5154f6416fad1428ed45afa41f37323587ac009",
+        strict: false,
+        cofactored: true,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "A with a torsion component, k multiple of 8",
+        public_key: "7764ddf619dee02e9c195ebee1961c6dc2989ef6b2193c8c9f84bc1846cf6590",
+        message: b"mixed A 4",
+        signature: "58f10fc759831f2602c201d910fad0ead75a817dfc2cc5432e191bf6048794c8\
+                    0fa2756505fac6430f6aacdd354df9d989c750495ffd15869043d69ee802ca06",
+        strict: true,
+        cofactored: true,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "R with a torsion component",
+        public_key: "58c1a2f118f1acf0772e0d26276e19afe98f006a383b3c7ccca07ec0ad56ba6b",
+        message: b"mixed R",
+        signature: "ba8e46dc158f09303c9b65aa06340c9128d30c20d5b079d0463d00afc1bcb96a\
+                    12110812a46e8ab1c526a67fcf3aa863b920e8c77a180eb36edec6da4198cf06",
+        strict: false,
+        cofactored: true,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "small order A and R, S = 0",
+        public_key: "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        message: b"small order",
+        signature: "0000000000000000000000000000000000000000000000000000000000000000\
+                    0000000000000000000000000000000000000000000000000000000000000000",
+        strict: false,
+        cofactored: true,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "small order A and R, cofactorless equation holds",
+        public_key: "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        message: b"small order 0",
+        signature: "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05\
+                    0000000000000000000000000000000000000000000000000000000000000000",
+        strict: false,
+        cofactored: true,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "non canonical R",
+        public_key: "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        message: b"non canonical R",
+        signature: "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f\
+                    0000000000000000000000000000000000000000000000000000000000000000",
+        strict: false,
+        cofactored: false,
+        zip215: true,
+    },
+    EdgeCase {
+        name: "non canonical A",
+        public_key: "0100000000000000000000000000000000000000000000000000000000000080",
+        message: b"non canonical A",
+        signature: "0000000000000000000000000000000000000000000000000000000000000000\
+                    0000000000000000000000000000000000000000000000000000000000000000",
+        strict: false,
+        cofactored: false,
+        zip215: true,
+    },
+];
+
+// the 12 cases of ed25519-speccheck, from "Taming the many EdDSAs"
+// (Chalkias, Garillot, Nikolaenko), as (message, public key, signature)
+// with the expected results of the strict, cofactored and ZIP-215 modes:
+//
+// * 0-2: small order A and/or R, both equations hold
+// * 3: mixed order A and R, both equations hold
+// * 4-5: mixed order A and R, only the cofactored equation holds
+// * 6-7: S is not reduced, only rejected by the S < L check
+// * 8-9: small order R, canonical and not canonical
+// * 10-11: non canonical encoding of a small order A, only accepted by ZIP-215
+const SPECCHECK_CASES: [(&str, &str, &str, [bool; 3]); 12] = [
+    (
+        "8c93255d71dcab10e8f379c26200f3c7bd5f09d9bc3068d3ef4edeb4853022b6",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a\
+         0000000000000000000000000000000000000000000000000000000000000000",
+        [false, true, true],
+    ),
+    (
+        "9bd9f44f4dcc75bd531b56b2cd280b0bb38fc1cd6d1230e14861d861de092e79",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+        "f7badec5b8abeaf699583992219b7b223f1df3fbbea919844e3f7c554a43dd43\
+         a5bb704786be79fc476f91d3f3f89b03984d8068dcf1bb7dfc6637b45450ac04",
+        [false, true, true],
+    ),
+    (
+        "aebf3f2601a0c8c5d39cc7d8911642f740b78168218da8471772b35f9d35b9ab",
+        "f7badec5b8abeaf699583992219b7b223f1df3fbbea919844e3f7c554a43dd43",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa\
+         8c4bd45aecaca5b24fb97bc10ac27ac8751a7dfe1baff8b953ec9f5833ca260e",
+        [false, true, true],
+    ),
+    (
+        "9bd9f44f4dcc75bd531b56b2cd280b0bb38fc1cd6d1230e14861d861de092e79",
+        "cdb267ce40c5cd45306fa5d2f29731459387dbf9eb933b7bd5aed9a765b88d4d",
+        "9046a64750444938de19f227bb80485e92b83fdb4b6506c160484c016cc1852f\
+         87909e14428a7a1d62e9f22f3d3ad7802db02eb2e688b6c52fcd6648a98bd009",
+        [true, true, true],
+    ),
+    (
+        "e47d62c63f830dc7a6851a0b1f33ae4bb2f507fb6cffec4011eaccd55b53f56c",
+        "cdb267ce40c5cd45306fa5d2f29731459387dbf9eb933b7bd5aed9a765b88d4d",
+        "160a1cb0dc9c0258cd0a7d23e94d8fa878bcb1925f2c64246b2dee1796bed512\
+         5ec6bc982a269b723e0668e540911a9a6a58921d6925e434ab10aa7940551a09",
+        [false, true, true],
+    ),
+    (
+        "e47d62c63f830dc7a6851a0b1f33ae4bb2f507fb6cffec4011eaccd55b53f56c",
+        "cdb267ce40c5cd45306fa5d2f29731459387dbf9eb933b7bd5aed9a765b88d4d",
+        "21122a84e0b5fca4052f5b1235c80a537878b38f3142356b2c2384ebad4668b7\
+         e40bc836dac0f71076f9abe3a53f9c03c1ceeeddb658d0030494ace586687405",
+        [false, true, true],
+    ),
+    (
+        "85e241a07d148b41e47d62c63f830dc7a6851a0b1f33ae4bb2f507fb6cffec40",
+        "442aad9f089ad9e14647b1ef9099a1ff4798d78589e66f28eca69c11f582a623",
+        "e96f66be976d82e60150baecff9906684aebb1ef181f67a7189ac78ea23b6c0e\
+         547f7690a0e2ddcd04d87dbc3490dc19b3b3052f7ff0538cb68afb369ba3a514",
+        [false, false, false],
+    ),
+    (
+        "85e241a07d148b41e47d62c63f830dc7a6851a0b1f33ae4bb2f507fb6cffec40",
+        "442aad9f089ad9e14647b1ef9099a1ff4798d78589e66f28eca69c11f582a623",
+        "8ce5b96c8f26d0ab6c47958c9e68b937104cd36e13c33566acd2fe8d38aa1942\
+         7e71f98a473474f2f13f06f97c20d58cc3f54b8bd0d272f42b695dd7e89a8c22",
+        [false, false, false],
+    ),
+    (
+        "9bedc267423725d473888631ebf45988bad3db83851ee85c85e241a07d148b41",
+        "f7badec5b8abeaf699583992219b7b223f1df3fbbea919844e3f7c554a43dd43",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f\
+         03be9678ac102edcd92b0210bb34d7428d12ffc5df5f37e359941266a4e35f0f",
+        [false, true, true],
+    ),
+    (
+        "9bedc267423725d473888631ebf45988bad3db83851ee85c85e241a07d148b41",
+        "f7badec5b8abeaf699583992219b7b223f1df3fbbea919844e3f7c554a43dd43",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff\
+         ca8c5b64cd208982aa38d4936621a4775aa233aa0505711d8fdcfdaa943d4908",
+        [false, false, true],
+    ),
+    (
+        "e96b7021eb39c1a163b6da4e3093dcd3f21387da4cc4572be588fafae23c155b",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "a9d55260f765261eb9b84e106f665e00b867287a761990d7135963ee0a7d59dc\
+         a5bb704786be79fc476f91d3f3f89b03984d8068dcf1bb7dfc6637b45450ac04",
+        [false, false, true],
+    ),
+    (
+        "39a591f5321bbe07fd5a23dc2f39d025d74526615746727ceefd6e82ae65c06f",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        "a9d55260f765261eb9b84e106f665e00b867287a761990d7135963ee0a7d59dc\
+         a5bb704786be79fc476f91d3f3f89b03984d8068dcf1bb7dfc6637b45450ac04",
+        [false, false, true],
+    ),
+];
+
+#[test]
+fn verification_modes() {
+    let modes = [
+        VerificationMode::Strict,
+        VerificationMode::Cofactored,
+        VerificationMode::Zip215,
+    ];
+
+    let xpub = XPrv::from_bytes_verified(D1_H0).unwrap().public();
+    let signature: Signature<u8> = Signature::from_slice(&D1_H0_SIGNATURE).unwrap();
+    for mode in modes.iter() {
+        assert!(xpub.verify_with_mode(MSG, &signature, *mode));
+        assert!(!xpub.verify_with_mode(b"other", &signature, *mode));
+    }
+
+    for (i, (message, public_key, signature, expected)) in SPECCHECK_CASES.iter().enumerate() {
+        let mut pk = [0u8; XPUB_SIZE];
+        pk[0..32].copy_from_slice(&hex::decode(public_key).unwrap());
+        let xpub = XPub::from_bytes(pk);
+        let message = hex::decode(message).unwrap();
+        let signature: Signature<u8> =
+            Signature::from_slice(&hex::decode(signature).unwrap()).unwrap();
+        for (mode, expected) in modes.iter().zip(expected.iter()) {
+            assert_eq!(
+                xpub.verify_with_mode(&message, &signature, *mode),
+                *expected,
+                "speccheck case {} with {:?}",
+                i,
+                mode
+            );
+        }
+    }
+
+    for case in EDGE_CASES.iter() {
+        let mut public_key = [0u8; XPUB_SIZE];
+        public_key[0..32].copy_from_slice(&hex::decode(case.public_key).unwrap());
+        let xpub = XPub::from_bytes(public_key);
+        let signature: Signature<u8> =
+            Signature::from_slice(&hex::decode(case.signature).unwrap()).unwrap();
+        let expected = [case.strict, case.cofactored, case.zip215];
+        for (mode, expected) in modes.iter().zip(expected.iter()) {
+            assert_eq!(
+                xpub.verify_with_mode(case.message, &signature, *mode),
+                *expected,
+                "{} with {:?}",
+                case.name,
+                mode
+            );
+        }
+    }
+}
+
+#[test]
+fn batch_verification() {
+    let modes = [
+        VerificationMode::Strict,
+        VerificationMode::Cofactored,
+        VerificationMode::Zip215,
+    ];
+
+    let root = XPrv::from_bytes_verified(D1).unwrap();
+    let messages: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; i as usize]).collect();
+    let mut batch: Vec<(XPub, &[u8], Signature<u8>)> = messages
+        .iter()
+        .enumerate()
+        .map(|(index, message)| {
+            let xprv = root.derive(DerivationScheme::V2, index as u32);
+            (xprv.public(), &message[..], xprv.sign(message))
+        })
+        .collect();
+    for mode in modes.iter() {
+        assert_eq!(verify_batch::<u8>(*mode, &[]), Ok(()));
+        assert_eq!(verify_batch(*mode, &batch), Ok(()));
+    }
+
+    // swapping two messages invalidates their signatures
+    batch.swap(0, 1);
+    let (first, second) = batch.split_at_mut(1);
+    core::mem::swap(&mut first[0].1, &mut second[0].1);
+    for mode in modes.iter() {
+        let error = verify_batch(*mode, &batch).unwrap_err();
+        assert_eq!(error.invalid_indices(), &[0, 1]);
+    }
+
+    // the edge cases are accepted as with the single verification
+    let signatures: Vec<(XPub, Signature<u8>)> = EDGE_CASES
+        .iter()
+        .map(|case| {
+            let mut public_key = [0u8; XPUB_SIZE];
+            public_key[0..32].copy_from_slice(&hex::decode(case.public_key).unwrap());
+            let signature = Signature::from_slice(&hex::decode(case.signature).unwrap());
+            (XPub::from_bytes(public_key), signature.unwrap())
+        })
+        .collect();
+    let mut batch = batch.split_off(2);
+    for (case, (xpub, signature)) in EDGE_CASES.iter().zip(signatures.iter()) {
+        batch.push((*xpub, case.message, signature.clone()));
+    }
+    for (position, mode) in modes.iter().enumerate() {
+        let expected: Vec<usize> = EDGE_CASES
+            .iter()
+            .enumerate()
+            .filter(|(_, case)| ![case.strict, case.cofactored, case.zip215][position])
+            .map(|(index, _)| index + 6)
+            .collect();
+        let error = verify_batch(*mode, &batch).unwrap_err();
+        assert_eq!(error.invalid_indices(), &expected[..], "{:?}", mode);
+
+        let valid: Vec<_> = batch
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !expected.contains(index))
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        assert_eq!(verify_batch(*mode, &valid), Ok(()), "{:?}", mode);
+    }
+}
+
+#[test]
+fn signature_variants() {
+    use cryptoxide::hashing::sha512;
+
+    fn rfc8032_xprv(secret: &str) -> XPrv {
+        let mut bytes = [0u8; XPRV_SIZE];
+        bytes[0..64].copy_from_slice(&sha512(&hex::decode(secret).unwrap()));
+        XPrv::normalize_bytes_ed25519(bytes)
+    }
+
+    // RFC 8032 section 7.2, Ed25519ctx with the context "foo"
+    let xprv = rfc8032_xprv("0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6");
+    let xpub = xprv.public();
+    assert_eq!(
+        hex::encode(xpub.public_key_slice()),
+        "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292"
+    );
+    let message = hex::decode("f726936d19c800494e3fdaff20b276a8").unwrap();
+    let signature: ContextSignature<u8> = xprv.sign_ctx(b"foo", &message).unwrap();
+    assert_eq!(
+        hex::encode(signature.as_ref()),
+        "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a\
+         8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d"
+    );
+    assert!(xpub.verify_ctx(b"foo", &message, &signature));
+    assert!(xprv.verify_ctx(b"foo", &message, &signature));
+    assert!(!xpub.verify_ctx(b"bar", &message, &signature));
+    let pure: Signature<u8> = Signature::from_bytes(*signature.to_bytes());
+    assert!(!xpub.verify(&message, &pure));
+
+    // RFC 8032 section 7.3, Ed25519ph of "abc"
+    let xprv = rfc8032_xprv("833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42");
+    let xpub = xprv.public();
+    let mut prehash = Prehash::new();
+    prehash.update(b"a");
+    prehash.update(b"bc");
+    let signature: PrehashSignature<u8> = xprv.sign_prehashed(&[], prehash.clone()).unwrap();
+    assert_eq!(
+        hex::encode(signature.as_ref()),
+        "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+         31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406"
+    );
+    assert!(xpub.verify_prehashed(&[], prehash.clone(), &signature));
+    assert!(!xpub.verify_prehashed(b"foo", prehash, &signature));
+    let pure: Signature<u8> = Signature::from_bytes(*signature.to_bytes());
+    assert!(!xpub.verify(b"abc", &pure));
+
+    // the variants also sign with the derived keys
+    let child = XPrv::from_bytes_verified(D1)
+        .unwrap()
+        .derive(DerivationScheme::V2, 1);
+    let signature: ContextSignature<u8> = child.sign_ctx(b"context", MSG).unwrap();
+    assert!(child.public().verify_ctx(b"context", MSG, &signature));
+
+    assert_eq!(
+        child.sign_ctx::<u8>(&[], MSG),
+        Err(ContextError::InvalidLength(0))
+    );
+    assert_eq!(
+        ContextError::InvalidLength(0).to_string(),
+        "Invalid signature context length 0, expected 1 to 255 bytes with Ed25519ctx or at most 255 with Ed25519ph"
+    );
+    assert_eq!(
+        child.sign_prehashed::<u8>(&[0; MAX_CONTEXT_SIZE + 1], Prehash::new()),
+        Err(ContextError::InvalidLength(MAX_CONTEXT_SIZE + 1))
+    );
+    assert!(child
+        .sign_prehashed::<u8>(&[0; MAX_CONTEXT_SIZE], Prehash::new())
+        .is_ok());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_derive_range() {
+    let prv = XPrv::from_bytes_verified(D1).unwrap();
+    let range = 0x7fffff00..0x80000100;
+    assert_eq!(
+        prv.par_derive_range(DerivationScheme::V2, range.clone()),
+        prv.derive_range(DerivationScheme::V2, range)
+    );
+
+    let xpub = prv.public();
+    let children = xpub
+        .par_derive_range(DerivationScheme::V2, 0..1000)
+        .unwrap();
+    assert_eq!(
+        children,
+        xpub.derive_range(DerivationScheme::V2, 0..1000).unwrap()
+    );
+    assert_eq!(
+        children[999],
+        xpub.derive(DerivationScheme::V2, 999).unwrap()
+    );
+    assert_eq!(
+        xpub.par_derive_range(DerivationScheme::V2, 0x7fffff00..0x80000001),
+        Err(DerivationError::ExpectedSoftDerivation)
+    );
+}
+
+// Stack scanning: `run_on_owned_stack` runs a function on a thread whose
+// stack is a zeroed buffer allocated by the test. Once the thread has been
+// joined, the buffer holds whatever the function left behind on its stack,
+// and can be searched for copies of a secret. This doesn't depend on the
+// optimization level, and only reads initialized memory owned by the test.
+#[cfg(all(unix, not(miri)))]
+mod stack_scan {
+    use std::alloc::{alloc_zeroed, dealloc, Layout};
+    use std::ffi::c_void;
+    use std::mem::MaybeUninit;
+    use std::ptr;
+
+    const STACK_SIZE: usize = 1024 * 1024;
+    const STACK_ALIGN: usize = 64 * 1024;
+    const PADDING_SIZE: usize = 64 * 1024;
+
+    extern "C" fn trampoline(f: *mut c_void) -> *mut c_void {
+        // `f` is the pointer given to `pthread_create` by `run_on_owned_stack`
+        let f = unsafe { &mut *(f as *mut &mut dyn FnMut()) };
+        run_deep(*f);
+        ptr::null_mut()
+    }
+
+    // run `f` below a padding frame, so that the frames of whatever runs
+    // after it returns, up to the exit of the thread, don't overwrite its own
+    #[inline(never)]
+    fn run_deep(f: &mut dyn FnMut()) {
+        let padding = [0u8; PADDING_SIZE];
+        std::hint::black_box(&padding);
+        f()
+    }
+
+    /// run `f` to completion on a zeroed stack, and return the content of the stack
+    pub fn run_on_owned_stack(f: impl FnOnce()) -> Vec<u8> {
+        let layout = Layout::from_size_align(STACK_SIZE, STACK_ALIGN).unwrap();
+        let mut f = Some(f);
+        let mut call = || (f.take().unwrap())();
+        let mut call: &mut dyn FnMut() = &mut call;
+        // the thread is joined before returning, so `call` and the stack outlive it
+        unsafe {
+            let stack = alloc_zeroed(layout);
+            assert!(!stack.is_null());
+            let mut attr = MaybeUninit::<libc::pthread_attr_t>::uninit();
+            assert_eq!(libc::pthread_attr_init(attr.as_mut_ptr()), 0);
+            assert_eq!(
+                libc::pthread_attr_setstack(attr.as_mut_ptr(), stack as *mut c_void, STACK_SIZE),
+                0
+            );
+            let mut thread = MaybeUninit::<libc::pthread_t>::uninit();
+            assert_eq!(
+                libc::pthread_create(
+                    thread.as_mut_ptr(),
+                    attr.as_ptr(),
+                    trampoline,
+                    &mut call as *mut &mut dyn FnMut() as *mut c_void,
+                ),
+                0
+            );
+            assert_eq!(libc::pthread_join(thread.assume_init(), ptr::null_mut()), 0);
+            libc::pthread_attr_destroy(attr.as_mut_ptr());
+            let content = std::slice::from_raw_parts(stack, STACK_SIZE).to_vec();
+            dealloc(stack, layout);
+            content
+        }
+    }
+
+    pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    // leaves a copy of the secret on the stack, to check the scan finds it
+    #[inline(never)]
+    pub fn leak_copy(secret: &[u8]) {
+        let mut copy = [0u8; 32];
+        for (dst, src) in copy.iter_mut().zip(secret.iter()) {
+            unsafe { ptr::write_volatile(dst, *src) };
+        }
+    }
+}
+
+#[cfg(all(unix, not(miri)))]
+#[test]
+fn derive_leaves_no_secret_on_stack() {
+    use stack_scan::{contains, leak_copy, run_on_owned_stack};
+
+    let parent = Box::new(XPrv::from_bytes_verified(D1).unwrap());
+    let (kl, kr) = (&D1[0..32], &D1[32..64]);
+
+    let stack = run_on_owned_stack(|| leak_copy(kl));
+    assert!(contains(&stack, kl), "the stack scan doesn't find secrets");
+
+    let mut child = None;
+    let stack = run_on_owned_stack(|| {
+        child = Some(Box::new(parent.derive(DerivationScheme::V2, 0x80000000)))
+    });
+    assert_eq!(*child.unwrap(), XPrv::from_bytes_verified(D1_H0).unwrap());
+    assert!(!contains(&stack, kl));
+    assert!(!contains(&stack, kr));
+}
+
+#[cfg(all(unix, not(miri)))]
+#[test]
+fn soft_derive_leaves_no_secret_on_stack() {
+    use stack_scan::{contains, run_on_owned_stack};
+
+    let parent = Box::new(XPrv::from_bytes_verified(D1).unwrap());
+    let (kl, kr) = (&D1[0..32], &D1[32..64]);
+
+    let mut child = None;
+    let stack =
+        run_on_owned_stack(|| child = Some(Box::new(parent.derive(DerivationScheme::V2, 0))));
+    assert_eq!(
+        child.unwrap().public(),
+        parent.public().derive(DerivationScheme::V2, 0).unwrap()
+    );
+    assert!(!contains(&stack, kl));
+    assert!(!contains(&stack, kr));
+}
+
+#[cfg(all(unix, not(miri)))]
+#[test]
+fn normalization_leaves_no_secret_on_stack() {
+    use cryptoxide::hashing::sha2::Sha512;
+    use stack_scan::{contains, run_on_owned_stack};
+
+    // a secret made of a single repeated byte can be found in the byte
+    // swapped words of the SHA-512 message schedule, so this one is not
+    let mut secret = [0u8; 32];
+    for (i, b) in secret.iter_mut().enumerate() {
+        *b = (i as u8).wrapping_mul(37).wrapping_add(2);
+    }
+    let chain_code = [0x5au8; 32];
+    let hash = Sha512::new().update(&secret).finalize();
+
+    let mut xprv = None;
+    let stack = run_on_owned_stack(|| {
+        xprv = Some(Box::new(XPrv::from_nonextended_force(&secret, &chain_code)))
+    });
+    let xprv = xprv.unwrap().extended_secret_key();
+    // the normalization changes the first and last bytes of the scalar, so
+    // the hash can't be confused with the normalized key
+    assert_ne!(xprv[0], hash[0]);
+    assert_ne!(xprv[31], hash[31]);
+    assert!(!contains(&stack, &secret));
+    assert!(!contains(&stack, &hash[0..32]));
+
+    let stack = run_on_owned_stack(|| {
+        let _ = XPrv::from_nonextended_noforce(&secret, &chain_code).map(Box::new);
+    });
+    assert!(!contains(&stack, &secret));
+    assert!(!contains(&stack, &hash[0..32]));
+}
+
+#[cfg(all(unix, not(miri)))]
+#[test]
+fn sign_leaves_no_secret_on_stack() {
+    use cryptoxide::curve25519::Scalar;
+    use cryptoxide::hashing::sha2::Sha512;
+    use stack_scan::{contains, run_on_owned_stack};
+
+    let xprv = Box::new(XPrv::from_bytes_verified(D1_H0).unwrap());
+    let (kl, kr) = (&D1_H0[0..32], &D1_H0[32..64]);
+    let nonce_hash = Sha512::new().update(kr).update(MSG).finalize();
+    let nonce = Scalar::reduce_from_wide_bytes(&nonce_hash).to_bytes();
+
+    let mut signature = None;
+    let stack = run_on_owned_stack(|| signature = Some(Box::new(xprv.sign::<()>(MSG))));
+    assert_eq!(signature.unwrap().to_bytes(), &D1_H0_SIGNATURE);
+    assert!(!contains(&stack, kl));
+    assert!(!contains(&stack, kr));
+    assert!(!contains(&stack, &nonce_hash[0..32]));
+    assert!(!contains(&stack, &nonce));
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zeroize_xprv() {
+    use zeroize::{Zeroize, Zeroizing};
+
+    let mut xprv = XPrv::from_bytes_verified(D1).unwrap();
+    xprv.zeroize();
+    assert_eq!(xprv.as_ref(), &[0u8; XPRV_SIZE][..]);
+
+    let bytes: Zeroizing<[u8; XPRV_SIZE]> = XPrv::from_bytes_verified(D1).unwrap().into();
+    assert_eq!(&bytes[..], &D1[..]);
+}
+
+#[test]
+fn securemem_zero() {
+    let mut bytes = D1;
+    securemem::zero(&mut bytes);
+    assert_eq!(bytes, [0u8; XPRV_SIZE]);
+}
+
+#[cfg(all(feature = "mlock", target_os = "linux"))]
+#[test]
+fn secure_xprv() {
+    let xprv = XPrv::from_bytes_verified(D1).unwrap();
+    let secure = SecureXPrv::new(xprv.clone()).unwrap();
+    assert_eq!(secure.public(), xprv.public());
+
+    let signature: Signature<Vec<u8>> = secure.sign(b"message");
+    assert_eq!(signature, xprv.sign(b"message"));
+    assert!(secure.verify(b"message", &signature));
+
+    let child = secure.derive(DerivationScheme::V2, 0x80000000).unwrap();
+    assert_eq!(
+        child.public(),
+        XPrv::from_bytes_verified(D1_H0).unwrap().public()
+    );
+    assert_eq!(
+        format!("{:?}", child),
+        format!(
+            "SecureXPrv({:?})",
+            XPrv::from_bytes_verified(D1_H0).unwrap()
+        )
+    );
+}
+
+#[cfg(all(feature = "mlock", target_os = "linux", not(miri)))]
+#[test]
+fn secure_xprv_wipes_source() {
+    use stack_scan::{contains, run_on_owned_stack};
+
+    let xprv = Box::new(XPrv::from_bytes_verified(D1).unwrap());
+    let public = xprv.public();
+    let mut secure = None;
+    let stack = run_on_owned_stack(|| secure = Some(SecureXPrv::new(*xprv).unwrap()));
+    assert!(!contains(&stack, &D1[0..32]));
+    assert!(!contains(&stack, &D1[32..64]));
+    assert_eq!(secure.unwrap().public(), public);
+}
@@ -0,0 +1,253 @@
+//! Ed25519ctx and Ed25519ph signatures (RFC 8032)
+//!
+//! Ed25519ctx signs a message along with a context string, so that a
+//! signature made for one protocol is not valid for another. Ed25519ph
+//! signs the SHA-512 hash of the message, which can be computed
+//! incrementally with `Prehash` without holding the whole message in memory.
+//!
+//! Both prepend `dom2(phflag, context)` to the hashes of the signature
+//! scheme, and have their own signature types: neither can be verified as a
+//! pure Ed25519 signature or as one another.
+
+use core::error::Error;
+use core::fmt;
+use core::marker::PhantomData;
+
+use cryptoxide::constant_time::CtEqual;
+use cryptoxide::hashing::sha2::{Context512, Sha512};
+
+use super::hex;
+use super::key::{XPrv, XPub};
+use super::signature::{SignatureError, SIGNATURE_SIZE};
+use super::verification::{self, VerificationMode};
+
+const DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+
+/// Maximum size of the context string of Ed25519ctx and Ed25519ph
+pub const MAX_CONTEXT_SIZE: usize = 255;
+
+/// Size of the SHA-512 hash signed by Ed25519ph
+pub const PREHASH_SIZE: usize = 64;
+
+/// Possible errors with the context string of a signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextError {
+    /// the context is longer than `MAX_CONTEXT_SIZE`, or is empty with Ed25519ctx
+    InvalidLength(usize),
+}
+
+/// `dom2(phflag, context)` of RFC 8032
+struct Dom2 {
+    bytes: [u8; DOM2_PREFIX.len() + 2 + MAX_CONTEXT_SIZE],
+    len: usize,
+}
+
+impl Dom2 {
+    fn new(phflag: u8, context: &[u8]) -> Result<Self, ContextError> {
+        // the context of Ed25519ctx can't be empty, or it'd be pure Ed25519
+        let min = if phflag == 0 { 1 } else { 0 };
+        if context.len() < min || context.len() > MAX_CONTEXT_SIZE {
+            return Err(ContextError::InvalidLength(context.len()));
+        }
+        let mut bytes = [0u8; DOM2_PREFIX.len() + 2 + MAX_CONTEXT_SIZE];
+        let len = DOM2_PREFIX.len() + 2 + context.len();
+        bytes[..DOM2_PREFIX.len()].copy_from_slice(DOM2_PREFIX);
+        bytes[DOM2_PREFIX.len()] = phflag;
+        bytes[DOM2_PREFIX.len() + 1] = context.len() as u8;
+        bytes[DOM2_PREFIX.len() + 2..len].copy_from_slice(context);
+        Ok(Dom2 { bytes, len })
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// SHA-512 hash of a message signed with Ed25519ph, computed incrementally
+#[derive(Clone)]
+pub struct Prehash(Context512);
+
+impl Prehash {
+    pub fn new() -> Self {
+        Prehash(Sha512::new())
+    }
+
+    /// hash the next part of the message
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update_mut(data);
+    }
+
+    fn finalize(self) -> [u8; PREHASH_SIZE] {
+        self.0.finalize()
+    }
+}
+
+impl Default for Prehash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! signature_variant {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Clone)]
+        pub struct $name<T: ?Sized> {
+            bytes: [u8; SIGNATURE_SIZE],
+            _phantom: PhantomData<T>,
+        }
+        impl<T> $name<T> {
+            pub fn from_bytes(bytes: [u8; SIGNATURE_SIZE]) -> Self {
+                $name {
+                    bytes,
+                    _phantom: PhantomData,
+                }
+            }
+
+            pub fn from_slice(bytes: &[u8]) -> Result<Self, SignatureError> {
+                if bytes.len() != SIGNATURE_SIZE {
+                    return Err(SignatureError::InvalidLength(bytes.len()));
+                }
+                let mut buf = [0u8; SIGNATURE_SIZE];
+                buf[..].clone_from_slice(bytes);
+                Ok(Self::from_bytes(buf))
+            }
+
+            pub fn to_bytes(&self) -> &[u8; SIGNATURE_SIZE] {
+                &self.bytes
+            }
+        }
+        impl<T> PartialEq for $name<T> {
+            fn eq(&self, rhs: &$name<T>) -> bool {
+                self.bytes.ct_eq(&rhs.bytes).into()
+            }
+        }
+        impl<T> Eq for $name<T> {}
+        impl<T> fmt::Display for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(self.as_ref()))
+            }
+        }
+        impl<T> fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", hex::encode(self.as_ref()))
+            }
+        }
+        impl<T> AsRef<[u8]> for $name<T> {
+            fn as_ref(&self) -> &[u8] {
+                &self.bytes
+            }
+        }
+    };
+}
+
+signature_variant!(
+    /// an Ed25519ctx signature with an associated type tag
+    ContextSignature
+);
+signature_variant!(
+    /// an Ed25519ph signature with an associated type tag
+    PrehashSignature
+);
+
+impl XPrv {
+    /// sign the message with Ed25519ctx and the given context, which must
+    /// be 1 to `MAX_CONTEXT_SIZE` bytes long
+    pub fn sign_ctx<T>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+    ) -> Result<ContextSignature<T>, ContextError> {
+        let dom2 = Dom2::new(0, context)?;
+        Ok(ContextSignature::from_bytes(
+            self.sign_with_dom(dom2.as_bytes(), message),
+        ))
+    }
+
+    /// sign the hashed message with Ed25519ph and the given context, which
+    /// may be empty and is at most `MAX_CONTEXT_SIZE` bytes long
+    pub fn sign_prehashed<T>(
+        &self,
+        context: &[u8],
+        prehash: Prehash,
+    ) -> Result<PrehashSignature<T>, ContextError> {
+        let dom2 = Dom2::new(1, context)?;
+        let hash = prehash.finalize();
+        Ok(PrehashSignature::from_bytes(
+            self.sign_with_dom(dom2.as_bytes(), &hash),
+        ))
+    }
+
+    /// verify an Ed25519ctx signature, see `XPub::verify_ctx`
+    pub fn verify_ctx<T>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &ContextSignature<T>,
+    ) -> bool {
+        self.public().verify_ctx(context, message, signature)
+    }
+
+    /// verify an Ed25519ph signature, see `XPub::verify_prehashed`
+    pub fn verify_prehashed<T>(
+        &self,
+        context: &[u8],
+        prehash: Prehash,
+        signature: &PrehashSignature<T>,
+    ) -> bool {
+        self.public().verify_prehashed(context, prehash, signature)
+    }
+}
+
+impl XPub {
+    /// verify an Ed25519ctx signature, with the rules of `VerificationMode::Strict`
+    pub fn verify_ctx<T>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &ContextSignature<T>,
+    ) -> bool {
+        match Dom2::new(0, context) {
+            Ok(dom2) => verification::verify(
+                VerificationMode::Strict,
+                dom2.as_bytes(),
+                self.public_key_bytes(),
+                message,
+                signature.to_bytes(),
+            ),
+            Err(_) => false,
+        }
+    }
+
+    /// verify an Ed25519ph signature, with the rules of `VerificationMode::Strict`
+    pub fn verify_prehashed<T>(
+        &self,
+        context: &[u8],
+        prehash: Prehash,
+        signature: &PrehashSignature<T>,
+    ) -> bool {
+        match Dom2::new(1, context) {
+            Ok(dom2) => verification::verify(
+                VerificationMode::Strict,
+                dom2.as_bytes(),
+                self.public_key_bytes(),
+                &prehash.finalize(),
+                signature.to_bytes(),
+            ),
+            Err(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContextError::InvalidLength(length) => write!(
+                f,
+                "Invalid signature context length {}, expected 1 to {} bytes with Ed25519ctx or at most {} with Ed25519ph",
+                length, MAX_CONTEXT_SIZE, MAX_CONTEXT_SIZE
+            ),
+        }
+    }
+}
+impl Error for ContextError {}
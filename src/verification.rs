@@ -0,0 +1,124 @@
+//! Ed25519 verification with a choice of validation rules
+//!
+//! Ed25519 implementations don't agree on the validity of some edge case
+//! signatures: non canonical encodings of the points, points of small order
+//! or with a torsion component, and the use of the cofactored equation or
+//! not (see "Taming the many EdDSAs", Chalkias et al.). Consensus critical
+//! verifiers need to pick a set of rules and stick to it.
+//!
+//! In every mode, the signatures with a non canonical `S` (not reduced
+//! modulo the order of the base point) are rejected.
+
+use core::convert::TryFrom;
+
+use cryptoxide::curve25519::{Ge, GePartial, Scalar};
+use cryptoxide::hashing::sha2::Sha512;
+
+use super::key::XPub;
+use super::point;
+use super::signature::Signature;
+
+/// Validation rules of an Ed25519 signature `(R, S)` made with the public key `A`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerificationMode {
+    /// strict RFC 8032 rules: `A` and `R` must be canonically encoded and not
+    /// of small order, and the signature must satisfy the cofactorless
+    /// equation `[S]B = R + [k]A`
+    Strict,
+    /// RFC 8032 rules: `A` and `R` must be canonically encoded, and the
+    /// signature must satisfy the cofactored equation `[8][S]B = [8]R + [8][k]A`
+    Cofactored,
+    /// ZIP-215 rules: non canonical encodings of `A` and `R` are accepted, and
+    /// the signature must satisfy the cofactored equation
+    Zip215,
+}
+
+/// the parts of a signature that enter the verification equation
+pub(crate) struct Equation {
+    // note: the decoded points are the negations of `A` and `R`
+    pub(crate) neg_a: Ge,
+    pub(crate) neg_r: Ge,
+    pub(crate) s: Scalar,
+    pub(crate) k: Scalar,
+}
+
+/// decode and check the points and the scalar of the signature, and compute
+/// `k = H(dom2 || R || A || message)`, where `dom2` is empty for pure Ed25519.
+/// The verification equation is left to check.
+pub(crate) fn equation(
+    mode: VerificationMode,
+    dom2: &[u8],
+    public_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8; 64],
+) -> Option<Equation> {
+    let r_bytes = <&[u8; 32]>::try_from(&signature[0..32]).unwrap();
+    let s_bytes = <&[u8; 32]>::try_from(&signature[32..64]).unwrap();
+
+    let s = Scalar::from_bytes_canonical(s_bytes)?;
+
+    let decode = |bytes| match mode {
+        VerificationMode::Zip215 => Ge::from_bytes(bytes),
+        VerificationMode::Strict | VerificationMode::Cofactored => point::decode_canonical(bytes),
+    };
+    let neg_a = decode(public_key)?;
+    let neg_r = decode(r_bytes)?;
+    if mode == VerificationMode::Strict
+        && (point::is_small_order(&neg_a) || point::is_small_order(&neg_r))
+    {
+        return None;
+    }
+
+    let hash = Sha512::new()
+        .update(dom2)
+        .update(r_bytes)
+        .update(public_key)
+        .update(message)
+        .finalize();
+    let k = Scalar::reduce_from_wide_bytes(&hash);
+
+    Some(Equation { neg_a, neg_r, s, k })
+}
+
+pub(crate) fn verify(
+    mode: VerificationMode,
+    dom2: &[u8],
+    public_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8; 64],
+) -> bool {
+    let equation = equation(mode, dom2, public_key, message, signature);
+    let Equation { neg_a, neg_r, s, k } = match equation {
+        Some(equation) => equation,
+        None => return false,
+    };
+
+    // [S]B - [k]A
+    let check = GePartial::double_scalarmult_vartime(&k, neg_a, &s);
+    match mode {
+        VerificationMode::Strict => check.to_bytes()[..] == signature[0..32],
+        VerificationMode::Cofactored | VerificationMode::Zip215 => {
+            // [8]([S]B - [k]A - R), computed as [4]([2]([S]B - [k]A) - [2]R)
+            let diff = (&check.double_full() + &neg_r.double().to_cached()).to_full();
+            point::is_identity(&diff.double().double())
+        }
+    }
+}
+
+impl XPub {
+    /// verify a signature with the given validation rules
+    pub fn verify_with_mode<T>(
+        &self,
+        message: &[u8],
+        signature: &Signature<T>,
+        mode: VerificationMode,
+    ) -> bool {
+        verify(
+            mode,
+            &[],
+            self.public_key_bytes(),
+            message,
+            signature.to_bytes(),
+        )
+    }
+}